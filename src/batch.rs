@@ -0,0 +1,159 @@
+use crate::{graph::{NodeData, NodeID}, narrow, phoneme::Phoneme, syllable::Syllable, word::Word};
+
+/// Sounds every word of a batch shares, for naming a line of products.
+///
+/// Whatever is shared but not given is taken from the first word generated. The onset shared by
+/// alliteration is the first syllable's. The nucleus shared by a vowel theme is that of the
+/// first syllable that isn't reduced, which
+/// [`FakeWordGenerator::generate_word`](crate::wordgen::FakeWordGenerator::generate_word)
+/// stresses.
+#[derive(Clone, Debug, Default)]
+pub struct BatchConstraints {
+    /// Every word starts with the same onset cluster.
+    pub alliterate: bool,
+    /// Every word has the same stressed nucleus.
+    pub vowel_theme: bool,
+    /// The shared onset, empty for words that start with a vowel.
+    pub onset: Option<Vec<Phoneme>>,
+    /// The shared nucleus.
+    pub nucleus: Option<Vec<Phoneme>>,
+}
+
+impl BatchConstraints {
+    pub fn is_active(&self) -> bool {
+        self.alliterate || self.vowel_theme
+    }
+
+    /// Returns true once everything that is shared is known.
+    pub fn is_settled(&self) -> bool {
+        (!self.alliterate || self.onset.is_some()) && (!self.vowel_theme || self.nucleus.is_some())
+    }
+
+    /// Returns the index of the syllable of `word` with the theme's nucleus: the first one that
+    /// isn't reduced, or the first one if they all are.
+    pub fn themed_syllable(word: &Word) -> usize {
+        word.syllables.iter().position(|syl| !narrow::is_reduced(syl)).unwrap_or(0)
+    }
+
+    /// Shares whatever isn't known yet from the first and themed syllables of `word`.
+    pub fn settle(&mut self, word: &Word) {
+        if self.alliterate && self.onset.is_none() {
+            self.onset = word.syllables.first().and_then(|syl| syl.split()).map(|(onset, _, _)| onset);
+        }
+        if self.vowel_theme && self.nucleus.is_none() {
+            let themed = word.syllables.get(Self::themed_syllable(word));
+            self.nucleus = themed.and_then(|syl| syl.split()).map(|(_, nucleus, _)| nucleus);
+        }
+    }
+
+    /// Returns whether the first syllable of a word being generated can move on to `next` and
+    /// still share what it should, for steering [`SonorityGraph`](crate::graph::SonorityGraph).
+    ///
+    /// The first syllable is steered towards the theme's nucleus too, which makes it the themed
+    /// syllable unless the nucleus is reduced.
+    pub fn allows_next(&self, syllable: &Syllable, next: NodeID) -> bool {
+        Self::allows(self.onset.as_deref(), self.nucleus.as_deref(), syllable.phonemes(), next.data)
+    }
+
+    /// Returns whether a syllable with `phonemes` so far can move on to `next` and still have
+    /// the `onset` and `nucleus` asked for, if any.
+    fn allows(onset: Option<&[Phoneme]>, nucleus: Option<&[Phoneme]>, phonemes: &[Phoneme], next: NodeData) -> bool {
+        let (shared_onset, shared_nucleus) = (onset, nucleus);
+        let onset = phonemes.iter().take_while(|phone| !phone.is_syllabic()).count();
+        let nucleus = phonemes[onset..].iter().take_while(|phone| phone.is_syllabic()).count();
+        let onset_done = shared_onset.is_none_or(|shared| shared.len() == onset);
+        let nucleus_done = shared_nucleus.is_none_or(|shared| shared.len() == nucleus);
+
+        match next {
+            NodeData::Start => true,
+            NodeData::Stop => nucleus_done,
+            NodeData::Phoneme(phoneme) if nucleus == 0 && !phoneme.is_syllabic() => {
+                shared_onset.is_none_or(|shared| shared.get(onset) == Some(&phoneme))
+            },
+            NodeData::Phoneme(phoneme) if phoneme.is_syllabic() && phonemes.len() == onset + nucleus => {
+                onset_done && shared_nucleus.is_none_or(|shared| shared.get(nucleus) == Some(&phoneme))
+            },
+            NodeData::Phoneme(_) => onset_done && nucleus_done,
+        }
+    }
+
+    fn syllable_allows(onset: Option<&[Phoneme]>, nucleus: Option<&[Phoneme]>, syllable: &Syllable) -> bool {
+        let phonemes = syllable.phonemes();
+        (0..phonemes.len()).all(|i| Self::allows(onset, nucleus, &phonemes[..i], NodeData::Phoneme(phonemes[i])))
+            && Self::allows(onset, nucleus, phonemes, NodeData::Stop)
+    }
+
+    /// Returns whether `word` shares what it should: the onset of its first syllable and the
+    /// nucleus of its themed syllable.
+    pub fn allows_word(&self, word: &Word) -> bool {
+        let Some(first) = word.syllables.first() else { return !self.is_active() };
+        let themed = &word.syllables[Self::themed_syllable(word)];
+        Self::syllable_allows(self.onset.as_deref(), None, first)
+            && Self::syllable_allows(None, self.nucleus.as_deref(), themed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::SonorityGraph;
+
+    use super::*;
+
+    fn word(arpabet: &str) -> Word {
+        Word::new(arpabet.split('.').map(|syllable| Syllable::from_arpabet(syllable).unwrap()).collect())
+    }
+
+    fn phonemes(arpabet: &str) -> Vec<Phoneme> {
+        arpabet.split_whitespace().map(|symbol| Phoneme::try_from_arpabet(symbol).unwrap()).collect()
+    }
+
+    fn alliterating(onset: &str) -> BatchConstraints {
+        BatchConstraints { alliterate: true, onset: Some(phonemes(onset)), ..BatchConstraints::default() }
+    }
+
+    fn themed(nucleus: &str) -> BatchConstraints {
+        BatchConstraints { vowel_theme: true, nucleus: Some(phonemes(nucleus)), ..BatchConstraints::default() }
+    }
+
+    #[test]
+    fn onsets_are_shared_whole() {
+        let batch = alliterating("S T");
+        assert!(batch.allows_word(&word("S T AA P")));
+        assert!(batch.allows_word(&word("S T IH . K IY")));
+        assert!(!batch.allows_word(&word("S AA P")));
+        assert!(!batch.allows_word(&word("S T R AA P")));
+        assert!(!batch.allows_word(&word("T AA P . S T AA P")));
+        assert!(batch.allows_next(&Syllable::from_arpabet("S").unwrap(), SonorityGraph::start_id(Phoneme::T)));
+        assert!(!batch.allows_next(&Syllable::from_arpabet("S T").unwrap(), SonorityGraph::start_id(Phoneme::R)));
+    }
+
+    #[test]
+    fn empty_onsets_are_shared_too() {
+        let batch = alliterating("");
+        assert!(batch.allows_word(&word("AA P")));
+        assert!(!batch.allows_word(&word("T AA P")));
+        assert!(!batch.allows_next(&Syllable::empty(), SonorityGraph::start_id(Phoneme::T)));
+        assert!(batch.allows_next(&Syllable::empty(), SonorityGraph::start_id(Phoneme::AA)));
+    }
+
+    #[test]
+    fn nuclei_are_shared_by_the_themed_syllable() {
+        let batch = themed("AY");
+        assert!(batch.allows_word(&word("T AY M")));
+        assert!(batch.allows_word(&word("AX . L AY T")));
+        assert!(!batch.allows_word(&word("T AA M . L AY T")));
+        assert!(!batch.allows_word(&word("T IY M")));
+    }
+
+    #[test]
+    fn themes_are_settled_from_the_first_word() {
+        let mut batch = BatchConstraints { alliterate: true, vowel_theme: true, ..BatchConstraints::default() };
+        assert!(!batch.is_settled());
+        let word = word("AX . B L UW M");
+        assert_eq!(BatchConstraints::themed_syllable(&word), 1);
+        batch.settle(&word);
+        assert!(batch.is_settled());
+        assert_eq!(batch.onset, Some(vec![]));
+        assert_eq!(batch.nucleus, Some(phonemes("UW")));
+    }
+}
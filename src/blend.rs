@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use crate::{g2p::G2p, phoneme::Phoneme, word::Word, wordgen::FakeWordGenerator};
+
+/// A portmanteau of two words, like brunch from breakfast and lunch.
+pub struct Blend {
+    pub word: Word,
+    pub spelling: String,
+    /// How plausible the model finds the blend, see [`FakeWordGenerator::score`].
+    pub score: f64,
+    /// How many phonemes of the first word begin the blend.
+    pub from_first: usize,
+    /// How many phonemes of the second word end the blend.
+    pub from_second: usize,
+    /// How many phonemes the words share where they join, like the /oʊ/ of motel.
+    pub overlap: usize,
+}
+
+/// Returns the places a word can be cut: between syllables, and between the onset and the
+/// rest of each syllable, so breakfast can give br and lunch can give unch.
+fn cut_points(word: &Word) -> Vec<usize> {
+    let mut cuts = Vec::new();
+    let mut start = 0;
+    for syllable in word.syllables.iter() {
+        cuts.push(start);
+        let onset = syllable.phonemes().iter().take_while(|phone| !phone.is_syllabic()).count();
+        if onset > 0 && onset < syllable.phonemes().len() {
+            cuts.push(start + onset);
+        }
+        start += syllable.phonemes().len();
+    }
+    cuts.push(start);
+    cuts
+}
+
+/// Finds every blend of the start of `first` and the end of `second`, cutting both words where
+/// their syllables or onsets end, most plausible first.
+///
+/// Where the phonemes before the cut in `first` are also the phonemes after the cut in
+/// `second`, they are only said once: motor and hotel blend into motel. Blends the model cannot
+/// generate and blends that are just one of the words are left out.
+pub fn blends(first: &Word, second: &Word, generator: &FakeWordGenerator, g2p: &G2p) -> Vec<Blend> {
+    let a = first.phonemes();
+    let b = second.phonemes();
+
+    let mut candidates: HashMap<Vec<Phoneme>, (usize, usize, usize)> = HashMap::new();
+    for from_first in cut_points(first).into_iter().filter(|cut| *cut > 0) {
+        for cut in cut_points(second).into_iter().filter(|cut| *cut < b.len()) {
+            let overlap = (1..=from_first.min(b.len() - cut))
+                .rev()
+                .find(|k| a[from_first - k..from_first] == b[cut..cut + k])
+                .unwrap_or(0);
+            // an overlap that takes up the rest of the second word leaves nothing of it
+            let overlap = if cut + overlap == b.len() { 0 } else { overlap };
+
+            let from_second = b.len() - cut - overlap;
+            let phonemes: Vec<Phoneme> = a[..from_first].iter().chain(b[cut + overlap..].iter()).copied().collect();
+            if phonemes == a || phonemes == b { continue };
+
+            let candidate = candidates.entry(phonemes).or_insert((from_first, from_second, overlap));
+            if overlap > candidate.2 {
+                *candidate = (from_first, from_second, overlap);
+            }
+        }
+    }
+
+    let mut blends: Vec<Blend> = candidates
+        .into_iter()
+        .filter_map(|(phonemes, (from_first, from_second, overlap))| {
+            let word = Word::syllabify(&phonemes);
+            let score = generator.score(&word)?;
+            Some(Blend { spelling: g2p.spell(&word), word, score, from_first, from_second, overlap })
+        })
+        .collect();
+    blends.sort_by(|x, y| y.score.total_cmp(&x.score).then_with(|| y.overlap.cmp(&x.overlap)).then_with(|| x.spelling.cmp(&y.spelling)));
+    blends
+}
@@ -0,0 +1,358 @@
+use std::str::FromStr;
+
+use crate::{batch::BatchConstraints, dialect::Dialect, filter::InventoryFilter, inflection::Inflection, inventory::Inventory, morphology::{Affix, PartOfSpeech}, notation::Notation, phoneme::Phoneme, phonology::Phonology, pseudowords::MatchConfig, respell::{self, RespellConfig, RespellingScheme, StressMark, Wikipedia}, rhyme::RhymeKind, word::Word};
+
+pub const USAGE: &str = "\
+Usage:
+  fake-word-generator [generate] [--count <n>] [--no-speak] [--enforce-sonority] [--sound-changes <file>] [--narrow] [--min-distance <n>] [--nearest <n>] [--prune <min-count>] [--metrics] [--rhyme <word>] [--rhyme-kind <kind>] [--part-of-speech <pos>] [--affix <affix>]... [--inflect] [batch options] [output options]
+  fake-word-generator stats [--top <n>] [--max-syllables <n>]
+  fake-word-generator sound-change <file> <word>...
+  fake-word-generator transcribe <x-sampa>... [output options]
+  fake-word-generator pronounce <spelling>... [--metrics] [output options]
+  fake-word-generator match <spelling>... [--matches <n>] [--phoneme-tolerance <n>] [--biphone-tolerance <x>] [--metrics] [output options]
+  fake-word-generator lookup <spelling>... [--prefix <phonemes>] [--suffix <phonemes>] [--limit <n>] [output options]
+  fake-word-generator blend <word> <word> [--top <n>] [output options]
+
+Batch options, for words that share sounds:
+  --alliterate               every word starts with the onset of the first
+  --vowel-theme              every word has the stressed vowel of the first
+  --onset <phonemes>         every word starts with these phonemes, e.g. \"S T\"
+  --nucleus <phonemes>       every word has this stressed vowel, e.g. AY
+
+Output options:
+  --dialect <ga|rp|au>       accent to respell and transcribe words in
+  --format <notation>        ipa (the default), x-sampa, kirshenbaum or espeak
+  --respelling <scheme>      wikipedia (the default), merriam-webster or plain
+  --stress <none|capitals|marks>
+  --separator <text>         put between respelled syllables
+
+Options for every command:
+  --inventory <file>         phoneme inventory to train on instead of English, see assets/inventories/english.ron
+  --phonology <file>         generate from phonotactic rules instead of a lexicon, see assets/phonologies/example.ron
+  --exclude <phonemes>       comma separated ARPAbet or X-SAMPA phonemes to never generate, e.g. TH,DH,ZH
+  --substitute <pairs>       comma separated replacements, e.g. TH=T,DH=D";
+
+pub enum Command {
+    /// Generates words, printing them and optionally reading them aloud.
+    Generate {
+        count: usize,
+        speak: bool,
+        enforce_sonority: bool,
+        /// Sound changes applied to every generated word. 
+        sound_changes: Option<String>,
+        /// Also prints a narrow transcription. 
+        narrow: bool,
+        /// The lowest novelty generated words may have, in inserted or deleted phonemes. 
+        min_distance: f32,
+        /// How many of the closest real words to print with each word's novelty, if any. 
+        nearest: usize,
+        /// Edges of the model seen fewer times than this are pruned before generating, if set. 
+        prune: Option<usize>,
+        /// Also prints neighborhood density and phonotactic probability. 
+        metrics: bool,
+        /// A dictionary word, or a word in X-SAMPA, that every word rhymes with. X-SAMPA between slashes is never looked up. 
+        rhyme: Option<String>,
+        rhyme_kind: RhymeKind,
+        batch: BatchConstraints,
+        /// Affixes learned from the dictionary, like un- or -ous, attached to every word. 
+        affixes: Vec<String>,
+        /// Gives every word a suffix that makes it this part of speech, unless a suffix was given. 
+        part_of_speech: Option<PartOfSpeech>,
+        /// Also prints the inflected forms of every word, the forms of its part of speech if it has one. 
+        inflect: bool,
+        output: OutputFormat,
+    },
+    /// Prints a summary of the trained model.
+    Stats {
+        top: usize,
+        max_syllables: usize,
+    },
+    /// Applies sound changes to dictionary words, showing each rule that fired. 
+    SoundChange {
+        file: String,
+        words: Vec<String>,
+    },
+    /// Respells and transcribes words written in X-SAMPA. 
+    Transcribe {
+        words: Vec<String>,
+        output: OutputFormat,
+    },
+    /// Pronounces spellings, real or not, and scores how plausible the model finds them. 
+    Pronounce {
+        words: Vec<String>,
+        /// Also prints neighborhood density and phonotactic probability. 
+        metrics: bool,
+        output: OutputFormat,
+    },
+    /// Generates pseudowords matched to dictionary words for experimental stimuli. 
+    Match {
+        words: Vec<String>,
+        config: MatchConfig,
+        /// Also prints neighborhood density and phonotactic probability. 
+        metrics: bool,
+        output: OutputFormat,
+    },
+    /// Looks words up in the dictionary, with their homophones, or finds the words starting or ending with some phonemes. 
+    Lookup {
+        words: Vec<String>,
+        prefix: Vec<Phoneme>,
+        suffix: Vec<Phoneme>,
+        /// How many words starting or ending with the phonemes to print. 
+        limit: usize,
+        output: OutputFormat,
+    },
+    /// Blends two words, dictionary spellings or IPA, into portmanteaus like brunch. 
+    Blend {
+        words: Vec<String>,
+        /// How many of the most plausible blends to print. 
+        top: usize,
+        output: OutputFormat,
+    },
+}
+
+impl Command {
+    /// Returns the output options of commands that print words. 
+    pub fn output_mut(&mut self) -> Option<&mut OutputFormat> {
+        match self {
+            Self::Generate { output, .. }
+            | Self::Transcribe { output, .. }
+            | Self::Pronounce { output, .. }
+            | Self::Match { output, .. }
+            | Self::Lookup { output, .. }
+            | Self::Blend { output, .. } => Some(output),
+            Self::Stats { .. } | Self::SoundChange { .. } => None,
+        }
+    }
+}
+
+/// How words are respelled and transcribed when they are printed. 
+#[derive(Default)]
+pub struct OutputFormat {
+    pub dialect: Dialect,
+    pub notation: Notation,
+    /// Respells words the way they have always been printed, with the Wikipedia key and no stress, if none is given. 
+    pub respelling: Option<Box<dyn RespellingScheme>>,
+    /// Overrides the stress marking of the respelling scheme. 
+    pub stress: Option<StressMark>,
+    /// Overrides the syllable separator of the respelling scheme. 
+    pub separator: Option<String>,
+}
+
+impl OutputFormat {
+    const OPTIONS: [&'static str; 5] = ["--dialect", "--format", "--respelling", "--stress", "--separator"];
+
+    /// Sets one of the output options to `value`. 
+    fn set(&mut self, option: &str, value: String) -> Result<(), String> {
+        match option {
+            "--dialect" => self.dialect = Dialect::parse(&value).ok_or(format!("Unknown dialect: {}", value))?,
+            "--format" => self.notation = Notation::parse(&value).ok_or(format!("Unknown notation: {}", value))?,
+            "--respelling" => self.respelling = Some(respell::scheme(&value).ok_or(format!("Unknown respelling scheme: {}", value))?),
+            "--stress" => self.stress = Some(StressMark::parse(&value).ok_or(format!("Unknown stress marking: {}", value))?),
+            _ => self.separator = Some(value),
+        }
+        Ok(())
+    }
+
+    pub fn respell(&self, word: &Word) -> String {
+        let (scheme, mut config): (&dyn RespellingScheme, RespellConfig) = match &self.respelling {
+            Some(scheme) => (scheme.as_ref(), scheme.default_config()),
+            None => (&Wikipedia, RespellConfig::default()),
+        };
+        if let Some(stress) = self.stress {
+            config.stress = stress;
+        }
+        if let Some(separator) = &self.separator {
+            config.separator = separator.clone();
+        }
+        self.dialect.respell(word, scheme, &config)
+    }
+
+    /// Formats `word` like its [`Display`](std::fmt::Display) implementation. 
+    pub fn format(&self, word: &Word) -> String {
+        format!("{} ({})", self.respell(word), self.notation.transcribe(&self.dialect.syllables_ipa(word)))
+    }
+}
+
+pub struct Cli {
+    pub command: Command,
+    pub inventory: InventoryFilter,
+    /// Rules to generate from instead of a lexicon. 
+    pub phonology: Option<Phonology>,
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {}", flag))?;
+    value.parse().map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+fn parse_phoneme(arpabet: &str) -> Result<Phoneme, String> {
+    let arpabet = arpabet.trim();
+    Phoneme::try_from_arpabet(arpabet)
+        .or_else(|| Phoneme::try_from_arpabet(&arpabet.to_uppercase()))
+        .or_else(|| Phoneme::try_from_xsampa(arpabet))
+        .ok_or(format!("Unknown phoneme: {}", arpabet))
+}
+
+/// Parses phonemes separated by spaces or commas. 
+fn parse_phonemes(phonemes: &str) -> Result<Vec<Phoneme>, String> {
+    phonemes
+        .split([' ', ','])
+        .filter(|phoneme| !phoneme.is_empty())
+        .map(parse_phoneme)
+        .collect()
+}
+
+impl Cli {
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.peekable();
+
+        let name = match args.peek() {
+            Some(arg) if !arg.starts_with("--") => args.next().unwrap(),
+            _ => String::from("generate"),
+        };
+
+        let mut command = match name.as_str() {
+            "generate" => Command::Generate { count: 50, speak: true, enforce_sonority: false, sound_changes: None, narrow: false, min_distance: 0.0, nearest: 0, prune: None, metrics: false, rhyme: None, rhyme_kind: RhymeKind::default(), batch: BatchConstraints::default(), affixes: vec![], part_of_speech: None, inflect: false, output: OutputFormat::default() },
+            "stats" => Command::Stats { top: 10, max_syllables: 4 },
+            "sound-change" => Command::SoundChange { file: parse_value("sound-change", args.next())?, words: vec![] },
+            "transcribe" => Command::Transcribe { words: vec![], output: OutputFormat::default() },
+            "pronounce" => Command::Pronounce { words: vec![], metrics: false, output: OutputFormat::default() },
+            "match" => Command::Match { words: vec![], config: MatchConfig::default(), metrics: false, output: OutputFormat::default() },
+            "lookup" => Command::Lookup { words: vec![], prefix: vec![], suffix: vec![], limit: 20, output: OutputFormat::default() },
+            "blend" => Command::Blend { words: vec![], top: 10, output: OutputFormat::default() },
+            _ => return Err(format!("Unknown command: {}", name)),
+        };
+
+        let mut inventory_file = None;
+        let mut phonology_file = None;
+        let mut excluded = vec![];
+        let mut substitutions = vec![];
+        let mut prefix = None;
+        let mut suffix = None;
+        let mut onset = None;
+        let mut nucleus = None;
+
+        while let Some(arg) = args.next() {
+            if let Some(output) = command.output_mut().filter(|_| OutputFormat::OPTIONS.contains(&arg.as_str())) {
+                output.set(&arg, parse_value(&arg, args.next())?)?;
+                continue;
+            }
+            match (&mut command, arg.as_str()) {
+                (_, "--inventory") => inventory_file = Some(parse_value::<String>(&arg, args.next())?),
+                (_, "--phonology") => phonology_file = Some(parse_value::<String>(&arg, args.next())?),
+                (_, "--exclude") => excluded.push(parse_value::<String>(&arg, args.next())?),
+                (_, "--substitute") => substitutions.push(parse_value::<String>(&arg, args.next())?),
+                (Command::Generate { count, .. }, "--count") => *count = parse_value(&arg, args.next())?,
+                (Command::Generate { speak, .. }, "--no-speak") => *speak = false,
+                (Command::Generate { enforce_sonority, .. }, "--enforce-sonority") => *enforce_sonority = true,
+                (Command::Generate { sound_changes, .. }, "--sound-changes") => *sound_changes = Some(parse_value(&arg, args.next())?),
+                (Command::Generate { narrow, .. }, "--narrow") => *narrow = true,
+                (Command::Generate { min_distance, .. }, "--min-distance") => *min_distance = parse_value(&arg, args.next())?,
+                (Command::Generate { nearest, .. }, "--nearest") => *nearest = parse_value(&arg, args.next())?,
+                (Command::Generate { prune, .. }, "--prune") => *prune = Some(parse_value(&arg, args.next())?),
+                (Command::Generate { metrics, .. } | Command::Pronounce { metrics, .. } | Command::Match { metrics, .. }, "--metrics") => *metrics = true,
+                (Command::Generate { rhyme, .. }, "--rhyme") => *rhyme = Some(parse_value(&arg, args.next())?),
+                (Command::Generate { rhyme_kind, .. }, "--rhyme-kind") => {
+                    let name: String = parse_value(&arg, args.next())?;
+                    *rhyme_kind = RhymeKind::parse(&name).ok_or(format!("Unknown kind of rhyme: {}", name))?;
+                },
+                (Command::Generate { batch, .. }, "--alliterate") => batch.alliterate = true,
+                (Command::Generate { batch, .. }, "--vowel-theme") => batch.vowel_theme = true,
+                (Command::Generate { .. }, "--onset") => onset = Some(parse_value::<String>(&arg, args.next())?),
+                (Command::Generate { .. }, "--nucleus") => nucleus = Some(parse_value::<String>(&arg, args.next())?),
+                (Command::Generate { affixes, .. }, "--affix") => affixes.push(parse_value(&arg, args.next())?),
+                (Command::Generate { part_of_speech, .. }, "--part-of-speech") => {
+                    let name: String = parse_value(&arg, args.next())?;
+                    *part_of_speech = Some(PartOfSpeech::parse(&name).ok_or(format!("Unknown part of speech: {}", name))?);
+                },
+                (Command::Generate { inflect, .. }, "--inflect") => *inflect = true,
+                (Command::Match { config, .. }, "--matches") => config.matches = parse_value(&arg, args.next())?,
+                (Command::Match { config, .. }, "--phoneme-tolerance") => config.phoneme_tolerance = parse_value(&arg, args.next())?,
+                (Command::Match { config, .. }, "--biphone-tolerance") => config.biphone_tolerance = parse_value(&arg, args.next())?,
+                (Command::Stats { top, .. } | Command::Blend { top, .. }, "--top") => *top = parse_value(&arg, args.next())?,
+                (Command::Stats { max_syllables, .. }, "--max-syllables") => *max_syllables = parse_value(&arg, args.next())?,
+                (Command::SoundChange { words, .. }, word) if !word.starts_with("--") => words.push(word.to_lowercase()),
+                (Command::Lookup { .. }, "--prefix") => prefix = Some(parse_value::<String>(&arg, args.next())?),
+                (Command::Lookup { .. }, "--suffix") => suffix = Some(parse_value::<String>(&arg, args.next())?),
+                (Command::Lookup { limit, .. }, "--limit") => *limit = parse_value(&arg, args.next())?,
+                (Command::Transcribe { words, .. } | Command::Pronounce { words, .. } | Command::Match { words, .. } | Command::Lookup { words, .. } | Command::Blend { words, .. }, word) if !word.starts_with("--") => words.push(word.to_owned()),
+                _ => return Err(format!("Unknown argument for {}: {}", name, arg)),
+            }
+        }
+
+        let phonology = match phonology_file {
+            Some(file) => Some(Phonology::load(&file).map_err(|err| format!("Invalid phonology {}: {}", file, err))?),
+            None => None,
+        };
+        if let Some(file) = phonology.as_ref().and_then(|phonology| phonology.inventory.clone()) {
+            if inventory_file.is_some() {
+                return Err(String::from("--inventory cannot be used with a phonology that names its own inventory"));
+            }
+            inventory_file = Some(file);
+        }
+
+        // phonemes can only be parsed once the inventory they belong to is in use
+        if let Some(file) = inventory_file {
+            let loaded = Inventory::load(&file).map_err(|err| format!("Invalid inventory {}: {}", file, err))?;
+            loaded.install().ok_or(String::from("An inventory is already in use"))?;
+        }
+
+        let mut inventory = InventoryFilter::new();
+        for arpabet in excluded.iter().flat_map(|value| value.split(',')) {
+            inventory = inventory.exclude(parse_phoneme(arpabet)?);
+        }
+        for pair in substitutions.iter().flat_map(|value| value.split(',')) {
+            let (from, to) = pair.split_once('=').ok_or(format!("Invalid substitution: {}", pair))?;
+            inventory = inventory.substitute(parse_phoneme(from)?, parse_phoneme(to)?);
+        }
+        if let Command::Lookup { prefix: prefix_phonemes, suffix: suffix_phonemes, .. } = &mut command {
+            *prefix_phonemes = parse_phonemes(prefix.as_deref().unwrap_or_default())?;
+            *suffix_phonemes = parse_phonemes(suffix.as_deref().unwrap_or_default())?;
+        }
+        if let Command::Blend { words, .. } = &command {
+            if words.len() != 2 {
+                return Err(String::from("blend takes exactly two words"));
+            }
+        }
+        if let Command::Generate { batch, rhyme, affixes, part_of_speech, inflect, .. } = &mut command {
+            if let Some(onset) = onset {
+                let onset = parse_phonemes(&onset)?;
+                if let Some(syllabic) = onset.iter().find(|phoneme| phoneme.is_syllabic()) {
+                    return Err(format!("{:?} is syllabic, so it cannot be part of an onset", syllabic));
+                }
+                batch.alliterate = true;
+                batch.onset = Some(onset);
+            }
+            if let Some(nucleus) = nucleus {
+                let nucleus = parse_phonemes(&nucleus)?;
+                if nucleus.is_empty() {
+                    return Err(String::from("--nucleus needs at least one phoneme"));
+                }
+                if let Some(consonant) = nucleus.iter().find(|phoneme| !phoneme.is_syllabic()) {
+                    return Err(format!("{:?} is not syllabic, so it cannot be a nucleus", consonant));
+                }
+                batch.vowel_theme = true;
+                batch.nucleus = Some(nucleus);
+            }
+            if rhyme.is_some() && batch.is_active() {
+                return Err(String::from("--rhyme cannot be used with batch options"));
+            }
+            if rhyme.is_some() && (!affixes.is_empty() || part_of_speech.is_some()) {
+                return Err(String::from("--rhyme cannot be used with --affix or --part-of-speech"));
+            }
+            if *inflect && matches!(part_of_speech, Some(PartOfSpeech::Adjective | PartOfSpeech::Adverb)) {
+                return Err(String::from("--inflect only inflects nouns and verbs"));
+            }
+            // the endings and affixes are English, so they need English phonemes
+            let undefined = |phonemes: &[Phoneme]| phonemes.iter().find(|phoneme| !phoneme.is_defined()).copied();
+            if let Some(phoneme) = undefined(&Inflection::PHONEMES).filter(|_| *inflect) {
+                return Err(format!("--inflect needs {:?}, which the {} inventory doesn't have", phoneme, Inventory::current().name));
+            }
+            if let Some(phoneme) = undefined(&Affix::PHONEMES).filter(|_| !affixes.is_empty() || part_of_speech.is_some()) {
+                return Err(format!("--affix and --part-of-speech need {:?}, which the {} inventory doesn't have", phoneme, Inventory::current().name));
+            }
+        }
+
+        Ok(Self { command, inventory, phonology })
+    }
+}
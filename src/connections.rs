@@ -1,10 +1,10 @@
-use std::{collections::{hash_map::Entry, HashMap}, env, fs::{self, File}, io::Write, path::Path};
+use std::{collections::{hash_map::Entry, HashMap}, fs::{self, File}, io::Write, path::Path};
 
 use indicatif::ProgressIterator;
 use rand::rngs::ThreadRng;
 use serde::{Deserialize, Serialize};
 
-use crate::{graph::NodeData, logger::{ProgressBarElements, TerminalLogger, WorkIndex, WorkMessage}, syllablize::SyllablizedPhonemes, utils};
+use crate::{dot::{self, DotConfig}, inventory::Inventory, graph::NodeData, logger::{ProgressBarElements, TerminalLogger, WorkIndex, WorkMessage}, prune::{PruneConfig, PruneReport, PrunedEdge}, syllablize::SyllablizedPhonemes, utils};
 
 #[derive(Serialize, Deserialize)]
 pub struct SyllableConnections {
@@ -12,10 +12,10 @@ pub struct SyllableConnections {
 }
 
 impl SyllableConnections {
-    const CACHE_FILE: &'static str = "assets/internal/syllable-connections.ron";
+    const CACHE_FILE: &'static str = "syllable-connections.ron";
 
     fn cache_file() -> String {
-        env::current_dir().unwrap().to_str().unwrap().to_owned() + "/" + Self::CACHE_FILE
+        Inventory::current().cache_file(Self::CACHE_FILE)
     }
     pub fn try_read_cache() -> Result<Vec<u8>, std::io::Error> {
         fs::read(Self::cache_file())
@@ -53,15 +53,15 @@ impl SyllableConnections {
 
             for (i, syllable) in syllables.iter().enumerate() {
                 if i == 0 {
-                    self.add_edge(NodeData::Start, NodeData::Phoneme(syllable.first_phoneme()));
+                    self.add_edge(NodeData::Start, NodeData::Phoneme(syllable.first_phoneme()), 1);
                 }
 
                 match syllables.get(i + 1) {
                     Some(next_syl) => {
-                        self.add_edge(NodeData::Phoneme(syllable.last_phoneme()), NodeData::Phoneme(next_syl.first_phoneme()));
+                        self.add_edge(NodeData::Phoneme(syllable.last_phoneme()), NodeData::Phoneme(next_syl.first_phoneme()), 1);
                     },
                     None => {
-                        self.add_edge(NodeData::Phoneme(syllable.last_phoneme()), NodeData::Stop);
+                        self.add_edge(NodeData::Phoneme(syllable.last_phoneme()), NodeData::Stop, 1);
                     }
                 }
             }
@@ -77,25 +77,131 @@ impl SyllableConnections {
         logger.finish_work(writing_work);
     }
 
-    pub fn evaluate(&self, node_data: NodeData, rng: &mut ThreadRng) -> NodeData {
-        utils::weighted_random_choice(self.connections.get(&node_data).unwrap(), rng)
+    /// Picks the phoneme that starts the syllable following `node_data`. 
+    /// 
+    /// Returns none if nothing is known to follow `node_data`. 
+    pub fn evaluate(&self, node_data: NodeData, rng: &mut ThreadRng) -> Option<NodeData> {
+        utils::weighted_random_choice(self.connections.get(&node_data)?, rng)
     }
 
-    fn add_edge(&mut self, from: NodeData, to: NodeData) {
+    /// Picks the phoneme that starts the syllable following `node_data`, only choosing from what 
+    /// `allow` accepts. 
+    /// 
+    /// Returns none if nothing allowed is known to follow `node_data`. 
+    pub fn evaluate_with(&self, node_data: NodeData, rng: &mut ThreadRng, allow: &dyn Fn(NodeData) -> bool) -> Option<NodeData> {
+        let allowed: Vec<(usize, NodeData)> = self.connections
+            .get(&node_data)?
+            .iter()
+            .filter(|(_, next)| allow(*next))
+            .copied()
+            .collect();
+        utils::weighted_random_choice(&allowed, rng)
+    }
+
+    /// Returns the chance that a syllable starting with `to` follows `from`, or none if it never does. 
+    pub fn probability(&self, from: NodeData, to: NodeData) -> Option<f64> {
+        let edges = self.connections.get(&from)?;
+        let total: usize = edges.iter().map(|edge| edge.0).sum();
+        let (count, _) = edges.iter().find(|edge| edge.1 == to)?;
+        Some(*count as f64 / total as f64)
+    }
+
+    /// Removes connections that fall below the thresholds in `config`. 
+    /// 
+    /// The most common connection out of each phoneme is always kept so that generation can 
+    /// always move on from a syllable. 
+    pub fn prune(&mut self, config: &PruneConfig) -> PruneReport {
+        let mut report = PruneReport::new();
+
+        for (from, edges) in self.connections.iter_mut() {
+            let total: usize = edges.iter().map(|edge| edge.0).sum();
+            let Some(strongest) = edges.iter().map(|edge| edge.0).max() else { continue };
+
+            let mut kept_strongest = false;
+            let mut kept = Vec::new();
+            for (count, to) in edges.drain(..) {
+                if count == strongest && !kept_strongest {
+                    kept_strongest = true;
+                    kept.push((count, to));
+                } else if config.should_prune(count, total) {
+                    report.removed_edges.push(PrunedEdge::Connection { from: *from, to, count });
+                } else {
+                    kept.push((count, to));
+                }
+            }
+            *edges = kept;
+        }
+
+        report
+    }
+
+    /// Exports the connections to Graphviz DOT, with the phonemes that end a syllable in one 
+    /// cluster and the phonemes that start the next syllable in another. 
+    pub fn to_dot(&self, config: &DotConfig) -> String {
+        let mut froms: Vec<NodeData> = self.connections.keys().copied().collect();
+        froms.sort_by_key(|data| dot::data_name(*data));
+
+        let mut tos: Vec<NodeData> = self.connections
+            .values()
+            .flat_map(|edges| edges.iter().map(|edge| edge.1))
+            .collect();
+        tos.sort_by_key(|data| dot::data_name(*data));
+        tos.dedup();
+
+        let max = self.connections
+            .values()
+            .flat_map(|edges| edges.iter().map(|edge| edge.0))
+            .max()
+            .unwrap_or(1);
+
+        let mut res = String::from("digraph syllable_connections {\n    rankdir=LR;\n    node [shape=ellipse];\n");
+
+        res += "    subgraph cluster_end {\n        label=\"Syllable End\";\n";
+        for data in froms.iter() {
+            res += &format!("        \"End_{}\" [label=\"{}\"];\n", dot::data_name(*data), dot::data_label(*data));
+        }
+        res += "    }\n";
+
+        res += "    subgraph cluster_start {\n        label=\"Syllable Start\";\n";
+        for data in tos.iter() {
+            res += &format!("        \"Start_{}\" [label=\"{}\"];\n", dot::data_name(*data), dot::data_label(*data));
+        }
+        res += "    }\n";
+
+        for from in froms.iter() {
+            let edges = &self.connections[from];
+            let total: usize = edges.iter().map(|edge| edge.0).sum();
+            for (count, to) in edges.iter() {
+                if config.threshold.should_prune(*count, total) { continue };
+                res += &format!(
+                    "    \"End_{}\" -> \"Start_{}\" [{}];\n",
+                    dot::data_name(*from),
+                    dot::data_name(*to),
+                    config.edge_attributes(*count, total, max)
+                );
+            }
+        }
+
+        res += "}\n";
+        res
+    }
+
+    /// Adds `count` to the connection between two syllables, creating it if needed. 
+    pub fn add_edge(&mut self, from: NodeData, to: NodeData, count: usize) {
         match self.connections.entry(from) {
             Entry::Vacant(entry) => {
-                entry.insert(vec![(1, to)]);
+                entry.insert(vec![(count, to)]);
             },
             Entry::Occupied(mut entry) => {
                 let mut has_edge = false;
                 for edge in entry.get_mut().iter_mut() {
                     if edge.1 == to {
                         has_edge = true;
-                        edge.0 += 1;
+                        edge.0 += count;
                     }
                 }
                 if !has_edge {
-                    entry.get_mut().push((1, to));
+                    entry.get_mut().push((count, to));
                 }
             }
         }
@@ -0,0 +1,253 @@
+use crate::{narrow, phoneme::Phoneme, respell::{self, RespellConfig, RespellingScheme, Wikipedia}, syllable::Stress, word::Word};
+
+/// What follows TRAP words that have the BATH vowel in RP and Australian English. A bare /s/
+/// isn't one, since gas and mass keep the TRAP vowel.
+const BATH: [&[Phoneme]; 5] = [
+    &[Phoneme::F],
+    &[Phoneme::TH],
+    &[Phoneme::S, Phoneme::K],
+    &[Phoneme::S, Phoneme::P],
+    &[Phoneme::S, Phoneme::T],
+];
+
+/// An accent of English to pronounce words in.
+///
+/// The bundled lexicon is General American, so the other dialects are derived from it with
+/// heuristics: they only know about the English inventory's phonemes, and words from other
+/// inventories are left as they are.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Dialect {
+    #[default]
+    GeneralAmerican,
+    ReceivedPronunciation,
+    Australian,
+}
+
+impl Dialect {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ga" | "us" | "general-american" => Some(Self::GeneralAmerican),
+            "rp" | "uk" | "received-pronunciation" => Some(Self::ReceivedPronunciation),
+            "au" | "australian" => Some(Self::Australian),
+            _ => None,
+        }
+    }
+
+    fn is_rhotic(self) -> bool {
+        self == Self::GeneralAmerican
+    }
+
+    /// Respellings of vowels the English inventory doesn't have, tried before its own.
+    fn respellings(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::GeneralAmerican => &[],
+            Self::ReceivedPronunciation => &[
+                ("ire", "aɪə"),
+                ("our", "aʊə"),
+                ("air", "ɛə"),
+                ("eer", "ɪə"),
+                ("oor", "ʊə"),
+                ("ur", "ɜː"),
+                ("oh", "əʊ"),
+            ],
+            Self::Australian => &[
+                ("ire", "ɑeə"),
+                ("our", "æɔə"),
+                ("eer", "ɪə"),
+                ("oor", "ʊə"),
+                ("air", "eː"),
+                ("ur", "ɜː"),
+                ("ah", "ɐː"),
+                ("aw", "oː"),
+                ("oo", "ʉː"),
+                ("oh", "əʉ"),
+                ("ay", "æɪ"),
+                ("eye", "ɑe"),
+                ("ow", "æɔ"),
+                ("oy", "oɪ"),
+                ("u", "ɐ"),
+                ("o", "ɔ"),
+            ],
+        }
+    }
+
+    /// The vowel that a vowel and a following /r/ merge into in a non-rhotic dialect.
+    fn vowel_before_r(self, vowel: Phoneme) -> String {
+        let australian = self == Self::Australian;
+        let merged = match vowel {
+            Phoneme::AA | Phoneme::AE => if australian { "ɐː" } else { "ɑː" },
+            Phoneme::AO | Phoneme::OW => if australian { "oː" } else { "ɔː" },
+            Phoneme::EH | Phoneme::EY => if australian { "eː" } else { "ɛə" },
+            Phoneme::IH | Phoneme::IY => "ɪə",
+            Phoneme::UH | Phoneme::UW => "ʊə",
+            Phoneme::AY => if australian { "ɑeə" } else { "aɪə" },
+            Phoneme::AW => if australian { "æɔə" } else { "aʊə" },
+            Phoneme::OY => if australian { "oɪə" } else { "ɔɪə" },
+            Phoneme::AH | Phoneme::ER => "ɜː",
+            Phoneme::AX | Phoneme::AXR => "ə",
+            _ => return self.vowel(vowel, &[]) + "ə",
+        };
+        merged.to_owned()
+    }
+
+    /// The realization of `vowel` when it isn't followed by a dropped /r/, where `rest` is every
+    /// phoneme after it.
+    ///
+    /// Three lexical sets are split with heuristics based on what comes next. TRAP becomes BATH
+    /// before the endings in [`BATH`] (staff, bath, ask, last), and in RP also before /ns ntʃ/
+    /// (dance, branch). THOUGHT becomes LOT before /f θ s ŋ ɡ/ (off, cloth, loss, long, dog).
+    /// LOT is kept as a long PALM vowel at the end of a word (spa), before /ð/ (father), before
+    /// /lm/ (palm), and before a final /m/ or /m/ and a final vowel (calm, drama).
+    fn vowel(self, vowel: Phoneme, rest: &[Phoneme]) -> String {
+        let australian = self == Self::Australian;
+        let bath = BATH.iter().any(|ending| rest.starts_with(ending));
+        let nasal_cluster = matches!(rest, [Phoneme::N, Phoneme::S | Phoneme::CH, ..]);
+        let cloth = matches!(rest.first().copied(), Some(Phoneme::F | Phoneme::TH | Phoneme::S | Phoneme::NG | Phoneme::G));
+        let palm = match rest {
+            [] | [Phoneme::DH, ..] | [Phoneme::L, Phoneme::M, ..] | [Phoneme::M] => true,
+            [Phoneme::M, last] => last.is_syllabic(),
+            _ => false,
+        };
+
+        let realized = match (vowel, australian) {
+            (Phoneme::AA, false) => if palm { "ɑː" } else { "ɒ" },
+            (Phoneme::AA, true) => if palm { "ɐː" } else { "ɔ" },
+            (Phoneme::AE, false) if bath || nasal_cluster => "ɑː",
+            (Phoneme::AE, true) if bath => "ɐː",
+            (Phoneme::AO, false) => if cloth { "ɒ" } else { "ɔː" },
+            (Phoneme::AO, true) => if cloth { "ɔ" } else { "oː" },
+            (Phoneme::OW, false) => "əʊ",
+            (Phoneme::OW, true) => "əʉ",
+            (Phoneme::IY, _) => "iː",
+            (Phoneme::UW | Phoneme::UX, false) => "uː",
+            (Phoneme::UW | Phoneme::UX, true) => "ʉː",
+            (Phoneme::ER, _) => "ɜː",
+            (Phoneme::AXR, _) => "ə",
+            (Phoneme::EH, true) => "e",
+            (Phoneme::AH, true) => "ɐ",
+            (Phoneme::EY, true) => "æɪ",
+            (Phoneme::AY, true) => "ɑe",
+            (Phoneme::AW, true) => "æɔ",
+            (Phoneme::OY, true) => "oɪ",
+            _ => vowel.to_ipa(),
+        };
+        realized.to_owned()
+    }
+
+    /// Returns the IPA of each syllable of `word` in this dialect.
+    ///
+    /// Non-rhotic dialects drop /r/ after a vowel unless the next syllable starts with a vowel,
+    /// merging it into the vowel before it, and say unstressed /ɝ/ as /ə/.
+    pub fn syllables_ipa(self, word: &Word) -> Vec<String> {
+        if self == Self::GeneralAmerican {
+            return word.syllables.iter().map(|syl| syl.to_ipa()).collect();
+        }
+
+        // unstressed /ɝ/, as in the ER0 of the lexicon, is a schwa once the r is dropped
+        let stresses = narrow::stresses(word);
+        let phones: Vec<(usize, Phoneme)> = word.syllables
+            .iter()
+            .enumerate()
+            .flat_map(|(i, syl)| syl.phonemes().iter().map(move |phone| (i, *phone)))
+            .map(|(i, phone)| match phone == Phoneme::ER && stresses[i] == Stress::Unstressed {
+                true => (i, Phoneme::AXR),
+                false => (i, phone),
+            })
+            .collect();
+        let drops_r = |i: usize| {
+            !self.is_rhotic()
+                && phones[i].1 == Phoneme::R
+                && i > 0
+                && phones[i - 1].1.is_vowel()
+                && phones[i - 1].0 == phones[i].0
+                && !phones.get(i + 1).is_some_and(|next| next.1.is_syllabic())
+        };
+
+        let phonemes: Vec<Phoneme> = phones.iter().map(|(_, phone)| *phone).collect();
+        let mut result = vec![String::new(); word.syllables.len()];
+        for (i, (syl, phone)) in phones.iter().enumerate() {
+            let realized = if drops_r(i) {
+                String::new()
+            } else if !phone.is_vowel() {
+                phone.to_ipa().to_owned()
+            } else if i + 1 < phones.len() && drops_r(i + 1) {
+                self.vowel_before_r(*phone)
+            } else {
+                let rest = &phonemes[i + 1..];
+                let mut vowel = self.vowel(*phone, rest);
+                // the r of an r-colored vowel is still heard before another vowel
+                if matches!(*phone, Phoneme::ER | Phoneme::AXR) && rest.first().is_some_and(|next| next.is_syllabic()) {
+                    vowel += "ɹ";
+                }
+                vowel
+            };
+            result[*syl] += &realized;
+        }
+        result
+    }
+
+    pub fn to_ipa(self, word: &Word) -> String {
+        self.syllables_ipa(word).join(" ")
+    }
+
+    pub fn to_english(self, word: &Word) -> String {
+        self.respell(word, &Wikipedia, &RespellConfig::default())
+    }
+
+    /// Respells `word` in this dialect with any respelling scheme.
+    pub fn respell(self, word: &Word, scheme: &dyn RespellingScheme, config: &RespellConfig) -> String {
+        respell::respell(&self.syllables_ipa(word), &narrow::stresses(word), scheme, config, self.respellings())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syllable::Syllable;
+
+    use super::*;
+
+    fn rp(syllables: &[&str]) -> String {
+        Dialect::ReceivedPronunciation.to_ipa(&Word::new(syllables.iter().map(|arpabet| Syllable::from_arpabet(arpabet).unwrap()).collect()))
+    }
+    fn australian(syllables: &[&str]) -> String {
+        Dialect::Australian.to_ipa(&Word::new(syllables.iter().map(|arpabet| Syllable::from_arpabet(arpabet).unwrap()).collect()))
+    }
+
+    #[test]
+    fn palm_words_keep_a_long_vowel() {
+        assert_eq!(rp(&["F AA1", "DH ER0"]), "fɑː ðə");
+        assert_eq!(rp(&["K AA1 M"]), "kɑːm");
+        assert_eq!(rp(&["D R AA1", "M AH0"]), "dɹɑː mʌ");
+        assert_eq!(rp(&["S P AA1"]), "spɑː");
+        assert_eq!(rp(&["L AA1 T"]), "lɒt");
+        assert_eq!(australian(&["L AA1 T"]), "lɔt");
+    }
+
+    #[test]
+    fn bath_words_are_split_from_trap() {
+        assert_eq!(rp(&["B AE1 TH"]), "bɑːθ");
+        assert_eq!(rp(&["AE1 S K"]), "ɑːsk");
+        assert_eq!(rp(&["D AE1 N S"]), "dɑːns");
+        assert_eq!(rp(&["G AE1 S"]), "ɡæs");
+        assert_eq!(rp(&["M AE1 S"]), "mæs");
+        assert_eq!(rp(&["AE1 N T"]), "ænt");
+        assert_eq!(australian(&["D AE1 N S"]), "dæns");
+        assert_eq!(australian(&["L AE1 S T"]), "lɐːst");
+    }
+
+    #[test]
+    fn thought_words_are_split_from_cloth() {
+        assert_eq!(rp(&["L AO1 S"]), "lɒs");
+        assert_eq!(rp(&["K AO1 T"]), "kɔːt");
+    }
+
+    #[test]
+    fn non_rhotic_dialects_drop_r_after_vowels() {
+        assert_eq!(rp(&["K AA1 R"]), "kɑː");
+        assert_eq!(rp(&["B AH1", "T ER0"]), "bʌ tə");
+        assert_eq!(rp(&["K AE1", "R IY0"]), "kæ ɹiː");
+        assert_eq!(rp(&["B ER1 D"]), "bɜːd");
+        assert_eq!(australian(&["K AA1 R"]), "kɐː");
+        assert_eq!(Dialect::GeneralAmerican.to_ipa(&Word::new(vec![Syllable::from_arpabet("K AA1 R").unwrap()])), "kɑɹ");
+    }
+}
@@ -0,0 +1,64 @@
+use crate::{graph::{NodeData, NodeID}, phoneme::SyllablePart, prune::PruneConfig};
+
+/// Options for exporting the model to Graphviz DOT.
+#[derive(Clone, Copy, Debug)]
+pub struct DotConfig {
+    /// Edges that this would prune are left out of the export.
+    pub threshold: PruneConfig,
+    /// Labels each edge with the probability of it being picked from its node.
+    pub show_probabilities: bool,
+    /// Pen width of the most common edge. Other edges are scaled relative to it.
+    pub max_pen_width: f32,
+}
+
+impl Default for DotConfig {
+    fn default() -> Self {
+        Self {
+            threshold: PruneConfig { min_count: 1, min_probability: 0.0 },
+            show_probabilities: false,
+            max_pen_width: 8.0,
+        }
+    }
+}
+
+impl DotConfig {
+    /// Formats the attributes of an edge seen `count` times out of the `total` leaving its node,
+    /// where `max` is the count of the most common edge in the whole export.
+    pub fn edge_attributes(&self, count: usize, total: usize, max: usize) -> String {
+        let width = (self.max_pen_width * count as f32 / max.max(1) as f32).max(0.1);
+        if self.show_probabilities {
+            format!("penwidth={:.2}, label=\"{:.3}\"", width, count as f32 / total.max(1) as f32)
+        } else {
+            format!("penwidth={:.2}", width)
+        }
+    }
+}
+
+
+pub fn part_name(part: SyllablePart) -> String {
+    match part {
+        SyllablePart::Onset => String::from("Onset"),
+        SyllablePart::Nucleus => String::from("Nucleus"),
+        SyllablePart::Coda { layer } => format!("Coda {}", layer),
+    }
+}
+
+pub fn data_label(data: NodeData) -> String {
+    match data {
+        NodeData::Start => String::from("Start"),
+        NodeData::Stop => String::from("Stop"),
+        NodeData::Phoneme(phoneme) => format!("{} /{}/", phoneme.to_arpabet(), phoneme.to_ipa()),
+    }
+}
+
+pub fn data_name(data: NodeData) -> String {
+    match data {
+        NodeData::Start => String::from("Start"),
+        NodeData::Stop => String::from("Stop"),
+        NodeData::Phoneme(phoneme) => String::from(phoneme.to_arpabet()),
+    }
+}
+
+pub fn node_name(id: NodeID) -> String {
+    format!("{}_{}", part_name(id.part).replace(' ', ""), data_name(id.data))
+}
@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+
+use crate::phoneme::Phoneme;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Place {
+    Bilabial,
+    Labiodental,
+    Dental,
+    Alveolar,
+    Postalveolar,
+    Palatal,
+    Velar,
+    LabioVelar,
+    Glottal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Manner {
+    Stop,
+    Affricate,
+    Fricative,
+    Nasal,
+    Tap,
+    Lateral,
+    Rhotic,
+    Glide,
+    Vowel,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Height {
+    High,
+    Mid,
+    Low,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Backness {
+    Front,
+    Central,
+    Back,
+}
+
+impl Manner {
+    pub fn is_obstruent(self) -> bool {
+        matches!(self, Self::Stop | Self::Affricate | Self::Fricative)
+    }
+    pub fn is_sonorant(self) -> bool {
+        !self.is_obstruent()
+    }
+}
+
+
+/// The distinctive features of a phoneme.
+///
+/// Vowels have no place, and consonants have no height or backness. Diphthongs are described by
+/// the vowel they start from.
+///
+/// Every feature but the manner can be left out of an inventory file, and is then false or none.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PhonemeFeatures {
+    #[serde(default)]
+    pub voiced: bool,
+    #[serde(default)]
+    pub place: Option<Place>,
+    pub manner: Manner,
+    #[serde(default)]
+    pub height: Option<Height>,
+    #[serde(default)]
+    pub backness: Option<Backness>,
+    #[serde(default)]
+    pub rounded: bool,
+    #[serde(default)]
+    pub tense: bool,
+    #[serde(default)]
+    pub diphthong: bool,
+    #[serde(default)]
+    pub rhotic: bool,
+    #[serde(default)]
+    pub syllabic: bool,
+}
+
+impl PhonemeFeatures {
+    pub fn consonant(voiced: bool, place: Place, manner: Manner) -> Self {
+        Self {
+            voiced,
+            place: Some(place),
+            manner,
+            height: None,
+            backness: None,
+            rounded: false,
+            tense: false,
+            diphthong: false,
+            rhotic: false,
+            syllabic: false,
+        }
+    }
+    pub fn vowel(height: Height, backness: Backness, rounded: bool, tense: bool) -> Self {
+        Self {
+            voiced: true,
+            place: None,
+            manner: Manner::Vowel,
+            height: Some(height),
+            backness: Some(backness),
+            rounded,
+            tense,
+            diphthong: false,
+            rhotic: false,
+            syllabic: true,
+        }
+    }
+
+    pub fn with_rounding(mut self) -> Self {
+        self.rounded = true;
+        self
+    }
+    pub fn with_diphthong(mut self) -> Self {
+        self.diphthong = true;
+        self
+    }
+    pub fn with_rhotic(mut self) -> Self {
+        self.rhotic = true;
+        self
+    }
+    pub fn with_syllabic(mut self) -> Self {
+        self.syllabic = true;
+        self
+    }
+
+    /// Returns the number of features that differ between `self` and `other`.
+    pub fn difference(&self, other: &PhonemeFeatures) -> usize {
+        [
+            self.voiced != other.voiced,
+            self.place != other.place,
+            self.manner != other.manner,
+            self.height != other.height,
+            self.backness != other.backness,
+            self.rounded != other.rounded,
+            self.tense != other.tense,
+            self.diphthong != other.diphthong,
+            self.rhotic != other.rhotic,
+            self.syllabic != other.syllabic,
+        ].into_iter().filter(|differs| *differs).count()
+    }
+    /// The largest value [`PhonemeFeatures::difference`] can return.
+    pub const MAX_DIFFERENCE: usize = 10;
+}
+
+
+/// A set of phonemes that share some features, such as "voiceless stops" or "front vowels".
+///
+/// Each field left as `None` (or empty) matches any phoneme.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct NaturalClass {
+    pub voiced: Option<bool>,
+    pub places: Vec<Place>,
+    pub manners: Vec<Manner>,
+    pub heights: Vec<Height>,
+    pub backnesses: Vec<Backness>,
+    pub rounded: Option<bool>,
+    pub tense: Option<bool>,
+    pub diphthong: Option<bool>,
+    pub rhotic: Option<bool>,
+    pub syllabic: Option<bool>,
+}
+
+impl NaturalClass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn voiced(mut self, voiced: bool) -> Self {
+        self.voiced = Some(voiced);
+        self
+    }
+    pub fn place(mut self, place: Place) -> Self {
+        self.places.push(place);
+        self
+    }
+    pub fn manner(mut self, manner: Manner) -> Self {
+        self.manners.push(manner);
+        self
+    }
+    pub fn height(mut self, height: Height) -> Self {
+        self.heights.push(height);
+        self
+    }
+    pub fn backness(mut self, backness: Backness) -> Self {
+        self.backnesses.push(backness);
+        self
+    }
+    pub fn rounded(mut self, rounded: bool) -> Self {
+        self.rounded = Some(rounded);
+        self
+    }
+    pub fn tense(mut self, tense: bool) -> Self {
+        self.tense = Some(tense);
+        self
+    }
+    pub fn diphthong(mut self, diphthong: bool) -> Self {
+        self.diphthong = Some(diphthong);
+        self
+    }
+    pub fn rhotic(mut self, rhotic: bool) -> Self {
+        self.rhotic = Some(rhotic);
+        self
+    }
+    pub fn syllabic(mut self, syllabic: bool) -> Self {
+        self.syllabic = Some(syllabic);
+        self
+    }
+
+    pub fn vowels() -> Self {
+        Self::new().manner(Manner::Vowel)
+    }
+    pub fn consonants() -> Self {
+        Self::new()
+            .manner(Manner::Stop)
+            .manner(Manner::Affricate)
+            .manner(Manner::Fricative)
+            .manner(Manner::Nasal)
+            .manner(Manner::Tap)
+            .manner(Manner::Lateral)
+            .manner(Manner::Rhotic)
+            .manner(Manner::Glide)
+    }
+
+    /// Parses a plain description such as "voiceless stops", "front vowels" or "coronal nasals".
+    ///
+    /// Returns none if any word is not recognized.
+    pub fn parse(description: &str) -> Option<Self> {
+        let mut class = Self::new();
+
+        for word in description.split_whitespace() {
+            let word = word.to_lowercase();
+            let word = word.strip_suffix('s').filter(|stem| !stem.ends_with('s')).unwrap_or(&word);
+
+            class = match word {
+                "voiced" => class.voiced(true),
+                "voiceless" | "unvoiced" => class.voiced(false),
+
+                "stop" | "plosive" => class.manner(Manner::Stop),
+                "affricate" => class.manner(Manner::Affricate),
+                "fricative" => class.manner(Manner::Fricative),
+                "nasal" => class.manner(Manner::Nasal),
+                "tap" | "flap" => class.manner(Manner::Tap),
+                "lateral" => class.manner(Manner::Lateral),
+                "liquid" => class.manner(Manner::Lateral).manner(Manner::Rhotic),
+                "glide" | "semivowel" => class.manner(Manner::Glide),
+                "approximant" => class.manner(Manner::Lateral).manner(Manner::Rhotic).manner(Manner::Glide),
+                "obstruent" => class.manner(Manner::Stop).manner(Manner::Affricate).manner(Manner::Fricative),
+                "sonorant" => class
+                    .manner(Manner::Nasal)
+                    .manner(Manner::Tap)
+                    .manner(Manner::Lateral)
+                    .manner(Manner::Rhotic)
+                    .manner(Manner::Glide)
+                    .manner(Manner::Vowel),
+                "vowel" => class.manner(Manner::Vowel),
+                "consonant" => Self { manners: Self::consonants().manners, ..class },
+
+                "bilabial" => class.place(Place::Bilabial),
+                "labiodental" => class.place(Place::Labiodental),
+                "dental" => class.place(Place::Dental),
+                "alveolar" => class.place(Place::Alveolar),
+                "postalveolar" => class.place(Place::Postalveolar),
+                "palatal" => class.place(Place::Palatal),
+                "velar" => class.place(Place::Velar),
+                "glottal" => class.place(Place::Glottal),
+                "labial" => class.place(Place::Bilabial).place(Place::Labiodental).place(Place::LabioVelar),
+                "coronal" => class.place(Place::Dental).place(Place::Alveolar).place(Place::Postalveolar),
+                "dorsal" => class.place(Place::Palatal).place(Place::Velar).place(Place::LabioVelar),
+
+                "high" | "close" => class.height(Height::High),
+                "mid" => class.height(Height::Mid),
+                "low" | "open" => class.height(Height::Low),
+                "front" => class.backness(Backness::Front),
+                "central" => class.backness(Backness::Central),
+                "back" => class.backness(Backness::Back),
+
+                "rounded" => class.rounded(true),
+                "unrounded" => class.rounded(false),
+                "tense" => class.tense(true),
+                "lax" => class.tense(false),
+                "diphthong" => class.diphthong(true),
+                "monophthong" => class.diphthong(false).manner(Manner::Vowel),
+                "rhotic" | "r-colored" => class.rhotic(true),
+                "syllabic" => class.syllabic(true),
+                _ => return None,
+            };
+        }
+
+        Some(class)
+    }
+
+    pub fn matches(&self, phoneme: Phoneme) -> bool {
+        let features = phoneme.features();
+
+        fn one_of<T: PartialEq>(options: &[T], value: Option<T>) -> bool {
+            options.is_empty() || value.is_some_and(|value| options.contains(&value))
+        }
+        fn equals(expected: Option<bool>, value: bool) -> bool {
+            expected.is_none_or(|expected| expected == value)
+        }
+
+        equals(self.voiced, features.voiced)
+            && one_of(&self.places, features.place)
+            && one_of(&self.manners, Some(features.manner))
+            && one_of(&self.heights, features.height)
+            && one_of(&self.backnesses, features.backness)
+            && equals(self.rounded, features.rounded)
+            && equals(self.tense, features.tense)
+            && equals(self.diphthong, features.diphthong)
+            && equals(self.rhotic, features.rhotic)
+            && equals(self.syllabic, features.syllabic)
+    }
+
+    /// Returns every phoneme in the class.
+    pub fn phonemes(&self) -> Vec<Phoneme> {
+        Phoneme::all().into_iter().filter(|phoneme| self.matches(*phoneme)).collect()
+    }
+}
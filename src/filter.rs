@@ -0,0 +1,155 @@
+use core::fmt;
+use std::collections::{HashMap, HashSet};
+
+use crate::{connections::SyllableConnections, features::NaturalClass, graph::{NodeData, NodeID, SonorityGraph, SonorityGraphEdge, SonorityGraphNode}, phoneme::Phoneme, syllable::Syllable, syllablize::LexiconError, validate::{self, ValidationReport}};
+
+#[derive(Debug)]
+pub enum InventoryError {
+    /// A vowel was substituted with a consonant or the other way around.
+    IncompatibleSubstitution { from: Phoneme, to: Phoneme },
+    /// Nothing can be generated once the excluded phonemes are removed.
+    GenerationImpossible(ValidationReport),
+    /// No word can start with the onset and nucleus a batch asks for.
+    BatchImpossible,
+    /// The lexicon of the inventory could not be trained on.
+    Lexicon(LexiconError),
+}
+
+impl fmt::Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IncompatibleSubstitution { from, to } => write!(
+                f,
+                "cannot substitute {:?} with {:?}: only one of them can be a syllable nucleus",
+                from,
+                to
+            ),
+            Self::GenerationImpossible(report) => {
+                write!(f, "the restricted inventory cannot generate any words")?;
+                for defect in report.remaining.iter() {
+                    write!(f, "\n  {}", defect)?;
+                }
+                Ok(())
+            },
+            Self::BatchImpossible => write!(f, "no word can start with the onset and nucleus the batch asks for"),
+            Self::Lexicon(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for InventoryError {}
+
+impl From<LexiconError> for InventoryError {
+    fn from(error: LexiconError) -> Self {
+        Self::Lexicon(error)
+    }
+}
+
+
+/// Restricts the phonemes that can be generated.
+///
+/// Substitutions are applied first, so substituting a phoneme with an excluded one excludes it too.
+#[derive(Clone, Debug, Default)]
+pub struct InventoryFilter {
+    pub excluded: HashSet<Phoneme>,
+    pub substitutions: HashMap<Phoneme, Phoneme>,
+}
+
+impl InventoryFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn exclude(mut self, phoneme: Phoneme) -> Self {
+        self.excluded.insert(phoneme);
+        self
+    }
+    pub fn exclude_class(mut self, class: &NaturalClass) -> Self {
+        self.excluded.extend(class.phonemes());
+        self
+    }
+    pub fn substitute(mut self, from: Phoneme, to: Phoneme) -> Self {
+        self.substitutions.insert(from, to);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.excluded.is_empty() && self.substitutions.is_empty()
+    }
+
+    /// Returns what `phoneme` becomes under the filter, or none if it is excluded.
+    pub fn map(&self, phoneme: Phoneme) -> Option<Phoneme> {
+        let mapped = self.substitutions.get(&phoneme).copied().unwrap_or(phoneme);
+        match self.excluded.contains(&mapped) {
+            true => None,
+            false => Some(mapped),
+        }
+    }
+
+    /// Returns what `syllable` becomes under the filter, or none if it has an excluded phoneme.
+    pub fn map_syllable(&self, syllable: &Syllable) -> Option<Syllable> {
+        syllable.phonemes().iter().map(|phoneme| self.map(*phoneme)).collect::<Option<Vec<Phoneme>>>().map(Syllable::from_phonemes)
+    }
+
+    fn map_data(&self, data: NodeData) -> Option<NodeData> {
+        match data {
+            NodeData::Phoneme(phoneme) => Some(NodeData::Phoneme(self.map(phoneme)?)),
+            data => Some(data),
+        }
+    }
+    fn map_id(&self, id: NodeID) -> Option<NodeID> {
+        Some(NodeID { data: self.map_data(id.data)?, part: id.part })
+    }
+
+    /// Removes excluded phonemes from the graph and connections and merges substituted phonemes
+    /// into their replacements. Since choices are weighted by count, the remaining edges are
+    /// renormalized automatically.
+    ///
+    /// The model is repaired afterwards, and an error is returned if it can no longer generate words.
+    pub fn apply(&self, graph: &mut SonorityGraph, connections: &mut SyllableConnections) -> Result<ValidationReport, InventoryError> {
+        for (from, to) in self.substitutions.iter() {
+            if from.is_syllabic() != to.is_syllabic() {
+                return Err(InventoryError::IncompatibleSubstitution { from: *from, to: *to });
+            }
+        }
+
+        let mut nodes: HashMap<NodeID, SonorityGraphNode> = HashMap::new();
+        for (id, node) in graph.nodes.iter() {
+            let Some(new_id) = self.map_id(*id) else { continue };
+            let new_node = nodes.entry(new_id).or_insert_with(SonorityGraphNode::new);
+
+            for edge in node.outs.iter() {
+                let Some(new_to) = self.map_id(edge.to) else { continue };
+                // substitutions shouldn't turn a move between two phonemes into a loop
+                if new_to == new_id && edge.to != *id { continue };
+
+                match new_node.outs.iter_mut().find(|out| out.to == new_to) {
+                    Some(out) => out.count += edge.count,
+                    None => new_node.outs.push(SonorityGraphEdge { from: new_id, to: new_to, count: edge.count }),
+                }
+            }
+        }
+        graph.nodes = nodes;
+
+        let mut conns: HashMap<NodeData, Vec<(usize, NodeData)>> = HashMap::new();
+        for (from, edges) in connections.connections.iter() {
+            let Some(new_from) = self.map_data(*from) else { continue };
+            let new_edges = conns.entry(new_from).or_default();
+
+            for (count, to) in edges.iter() {
+                let Some(new_to) = self.map_data(*to) else { continue };
+                match new_edges.iter_mut().find(|edge| edge.1 == new_to) {
+                    Some(edge) => edge.0 += count,
+                    None => new_edges.push((*count, new_to)),
+                }
+            }
+        }
+        connections.connections = conns;
+
+        let report = validate::repair(graph, connections);
+        match report.is_valid() {
+            true => Ok(report),
+            false => Err(InventoryError::GenerationImpossible(report)),
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::{collections::HashMap, fs::{self, File}, hash::Hash, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{inventory::Inventory, lexicon::Lexicon, logger::{TerminalLogger, WorkIndex, WorkMessage}, phoneme::Phoneme, syllablize::SyllablizedPhonemes, word::Word};
+
+/// A symbol with the symbols around it, and the index of the context size it was taken at.
+type Context<I> = (usize, Vec<Option<I>>);
+
+/// Counts of what a symbol becomes given the symbols around it, at several context sizes.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "I: Serialize + Eq + Hash, O: Serialize + Eq + Hash",
+    deserialize = "I: Deserialize<'de> + Eq + Hash, O: Deserialize<'de> + Eq + Hash",
+))]
+struct ContextModel<I, O> {
+    counts: HashMap<Context<I>, HashMap<O, usize>>,
+}
+
+impl<I: Copy + Eq + Hash, O: Clone + Eq + Hash> ContextModel<I, O> {
+    /// The `(before, after)` context sizes tried in order, from the most specific to the symbol
+    /// on its own.
+    const CONTEXTS: [(usize, usize); 5] = [(3, 3), (2, 2), (1, 2), (1, 1), (0, 0)];
+    /// How many sightings it takes for a context to be trusted as much as the smaller ones.
+    const SMOOTHING: f64 = 10.0;
+
+    fn new() -> Self {
+        Self { counts: HashMap::new() }
+    }
+
+    fn key(input: &[I], i: usize, (before, after): (usize, usize)) -> Vec<Option<I>> {
+        (i as isize - before as isize..=(i + after) as isize)
+            .map(|j| usize::try_from(j).ok().and_then(|j| input.get(j).copied()))
+            .collect()
+    }
+
+    fn add(&mut self, input: &[I], i: usize, output: &O) {
+        for (level, context) in Self::CONTEXTS.iter().enumerate() {
+            let counts = self.counts.entry((level, Self::key(input, i, *context))).or_default();
+            *counts.entry(output.clone()).or_default() += 1;
+        }
+    }
+
+    /// Returns the likeliest output for the symbol at `i`.
+    ///
+    /// The chances at each context size are mixed, trusting larger contexts more the more often
+    /// they were seen, so a context seen in a handful of words can't outweigh the rest.
+    fn predict(&self, input: &[I], i: usize) -> Option<&O> {
+        let mut chances: HashMap<&O, f64> = HashMap::new();
+        for (level, context) in Self::CONTEXTS.iter().enumerate().rev() {
+            let Some(counts) = self.counts.get(&(level, Self::key(input, i, *context))) else { continue };
+            let total = counts.values().sum::<usize>() as f64;
+            let weight = total / (total + Self::SMOOTHING);
+            for chance in chances.values_mut() {
+                *chance *= 1.0 - weight;
+            }
+            for (output, count) in counts.iter() {
+                *chances.entry(output).or_default() += weight * *count as f64 / total;
+            }
+        }
+
+        chances
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then_with(|| output_rank(b.0).cmp(&output_rank(a.0))))
+            .map(|(output, _)| output)
+    }
+}
+
+// breaks ties the same way every run, since hash maps are unordered
+fn output_rank<O: Hash>(output: &O) -> u64 {
+    use std::hash::{DefaultHasher, Hasher};
+    let mut hasher = DefaultHasher::new();
+    output.hash(&mut hasher);
+    hasher.finish()
+}
+
+
+/// Converts between spellings and pronunciations.
+///
+/// Real words are looked up in the dictionary. Other spellings and pronunciations are converted
+/// letter by letter, or phoneme by phoneme, by models trained on the dictionary: each letter is
+/// aligned to zero, one or two phonemes (x is /k s/), and what a letter becomes is predicted
+/// from up to three letters on either side.
+pub struct G2p {
+    pub lexicon: Lexicon,
+    models: G2pModels,
+}
+
+/// What [`G2p`] learns from the dictionary, cached since training takes a while.
+#[derive(Serialize, Deserialize)]
+struct G2pModels {
+    letters: ContextModel<char, Vec<Phoneme>>,
+    sounds: ContextModel<Phoneme, String>,
+}
+
+impl G2p {
+    /// Rounds of aligning every word and counting the alignments.
+    const ALIGNMENT_ROUNDS: usize = 3;
+    const CACHE_FILE: &'static str = "g2p-model.ron";
+
+    fn cache_file() -> String {
+        Inventory::current().cache_file(Self::CACHE_FILE)
+    }
+
+    /// Loads the cached models, or trains them on the dictionary and caches them.
+    pub fn new(syl_phones: &SyllablizedPhonemes, logger: &mut TerminalLogger) -> Self {
+        let lexicon = Lexicon::new(syl_phones);
+
+        let read_work = logger.begin_work(WorkMessage::new("Reading", "Grapheme to Phoneme Model", WorkIndex::None));
+        let cached = fs::read(Self::cache_file())
+            .ok()
+            .and_then(|contents| ron::de::from_bytes::<G2pModels>(&contents).ok());
+        if let Some(models) = cached {
+            logger.finish_work(read_work);
+            return Self { lexicon, models };
+        }
+        logger.fail_work(read_work, "Retraining");
+
+        let work = logger.begin_work(WorkMessage::new("Training", "Grapheme to Phoneme Model", WorkIndex::None));
+        let g2p = Self { models: Self::train_models(&lexicon), lexicon };
+        let mut file = File::create(Self::cache_file()).unwrap();
+        file.write_all(ron::ser::to_string(&g2p.models).unwrap().as_bytes())
+            .expect("Failed to write to file");
+        logger.finish_work(work);
+        g2p
+    }
+
+    /// Trains on the dictionary without touching the cache.
+    pub fn train(syl_phones: &SyllablizedPhonemes) -> Self {
+        let lexicon = Lexicon::new(syl_phones);
+        Self { models: Self::train_models(&lexicon), lexicon }
+    }
+
+    fn train_models(lexicon: &Lexicon) -> G2pModels {
+        let mut models = G2pModels {
+            letters: ContextModel::new(),
+            sounds: ContextModel::new(),
+        };
+
+        // only the main pronunciation of each word is trained on
+        let mut words: Vec<(Vec<char>, Vec<Phoneme>)> = Vec::new();
+        for entry in lexicon.entries().iter().filter(|entry| entry.variant == 0) {
+            if entry.spelling.chars().all(|c| c.is_ascii_lowercase() || c == '\'') {
+                words.push((entry.spelling.chars().collect(), entry.word.phonemes()));
+            }
+        }
+
+        let mut aligner = Aligner::new(&words);
+        for _ in 1..Self::ALIGNMENT_ROUNDS {
+            aligner = aligner.realign(&words);
+        }
+
+        for (letters, phonemes) in words.iter() {
+            let Some(chunks) = aligner.align(letters, phonemes) else { continue };
+            for (i, chunk) in chunks.iter().enumerate() {
+                models.letters.add(letters, i, chunk);
+            }
+            for (j, spelled) in spell_phonemes(letters, &chunks).iter().enumerate() {
+                models.sounds.add(phonemes, j, spelled);
+            }
+        }
+
+        models
+    }
+
+    /// Returns the main dictionary pronunciation of a real word.
+    pub fn lookup(&self, spelling: &str) -> Option<&Word> {
+        self.lexicon.lookup(spelling).first().map(|entry| &entry.word)
+    }
+
+    /// Predicts a pronunciation from the spelling alone, even for real words.
+    ///
+    /// Returns none if the spelling has letters the model never saw or no sounds at all.
+    pub fn predict(&self, spelling: &str) -> Option<Word> {
+        let letters: Vec<char> = spelling.to_lowercase().chars().collect();
+        let mut phonemes = Vec::new();
+        for i in 0..letters.len() {
+            phonemes.extend(self.models.letters.predict(&letters, i)?.iter().copied());
+        }
+        match phonemes.is_empty() {
+            true => None,
+            false => Some(Word::syllabify(&phonemes)),
+        }
+    }
+
+    /// Pronounces a spelling, looking it up in the dictionary if it is a real word.
+    pub fn pronounce(&self, spelling: &str) -> Option<Word> {
+        self.lookup(spelling).cloned().or_else(|| self.predict(spelling))
+    }
+
+    /// Predicts a spelling from the pronunciation alone. Phonemes the model never saw are left out.
+    pub fn predict_spelling(&self, word: &Word) -> String {
+        let phonemes = word.phonemes();
+        (0..phonemes.len())
+            .filter_map(|j| self.models.sounds.predict(&phonemes, j))
+            .map(|spelled| spelled.as_str())
+            .collect()
+    }
+
+    /// Spells a pronunciation the way the dictionary does if it is a real word, picking the most
+    /// common of its homophones.
+    pub fn spell(&self, word: &Word) -> String {
+        match self.lexicon.homophones(word).first() {
+            Some(entry) => entry.spelling.clone(),
+            None => self.predict_spelling(word),
+        }
+    }
+}
+
+
+/// Aligns letters to the phonemes they spell, scoring each letter's chunk of phonemes by how
+/// often the letter spelled it in the last round.
+struct Aligner {
+    counts: HashMap<(char, Vec<Phoneme>), usize>,
+    totals: HashMap<char, usize>,
+}
+
+impl Aligner {
+    /// Starts from how often letters and phonemes, or pairs of phonemes, appear at about the
+    /// same place in a word.
+    fn new(words: &[(Vec<char>, Vec<Phoneme>)]) -> Self {
+        let mut aligner = Self { counts: HashMap::new(), totals: HashMap::new() };
+        for (letters, phonemes) in words.iter() {
+            for (i, letter) in letters.iter().enumerate() {
+                let position = i as f32 / letters.len() as f32;
+                for j in 0..phonemes.len() {
+                    if (position - j as f32 / phonemes.len() as f32).abs() >= 0.25 { continue };
+                    aligner.count(*letter, vec![phonemes[j]]);
+                    if let Some(next) = phonemes.get(j + 1) {
+                        aligner.count(*letter, vec![phonemes[j], *next]);
+                    }
+                }
+            }
+        }
+        aligner
+    }
+
+    fn count(&mut self, letter: char, chunk: Vec<Phoneme>) {
+        *self.counts.entry((letter, chunk)).or_default() += 1;
+        *self.totals.entry(letter).or_default() += 1;
+    }
+
+    fn realign(&self, words: &[(Vec<char>, Vec<Phoneme>)]) -> Self {
+        let mut aligner = Self { counts: HashMap::new(), totals: HashMap::new() };
+        for (letters, phonemes) in words.iter() {
+            let Some(chunks) = self.align(letters, phonemes) else { continue };
+            for (letter, chunk) in letters.iter().zip(chunks) {
+                aligner.count(*letter, chunk);
+            }
+        }
+        aligner
+    }
+
+    fn score(&self, letter: char, chunk: &[Phoneme]) -> f32 {
+        // silent letters and letters spelling two sounds start out unlikely, but are learned
+        let prior = match chunk.len() {
+            0 => 0.1,
+            1 => 1.0,
+            _ => 0.01,
+        };
+        let count = self.counts.get(&(letter, chunk.to_vec())).copied().unwrap_or(0) as f32;
+        let total = self.totals.get(&letter).copied().unwrap_or(0) as f32;
+        ((count + prior) / (total + 1.0)).ln()
+    }
+
+    /// Returns the chunk of phonemes each letter spells in the best alignment, or none if the
+    /// word has too many phonemes for its letters.
+    fn align(&self, letters: &[char], phonemes: &[Phoneme]) -> Option<Vec<Vec<Phoneme>>> {
+        let (n, m) = (letters.len(), phonemes.len());
+        // best[i][j] is the score and chunk size of aligning i letters to j phonemes
+        let mut best: Vec<Vec<Option<(f32, usize)>>> = vec![vec![None; m + 1]; n + 1];
+        best[0][0] = Some((0.0, 0));
+
+        for i in 0..n {
+            for j in 0..=m {
+                let Some((score, _)) = best[i][j] else { continue };
+                for size in 0..=2 {
+                    if j + size > m { break };
+                    let next = score + self.score(letters[i], &phonemes[j..j + size]);
+                    if best[i + 1][j + size].is_none_or(|(other, _)| next > other) {
+                        best[i + 1][j + size] = Some((next, size));
+                    }
+                }
+            }
+        }
+
+        best[n][m]?;
+        let mut chunks = vec![vec![]; n];
+        let mut j = m;
+        for i in (0..n).rev() {
+            let (_, size) = best[i + 1][j]?;
+            chunks[i] = phonemes[j - size..j].to_vec();
+            j -= size;
+        }
+        Some(chunks)
+    }
+}
+
+/// Turns the chunk of phonemes each letter spells into the letters each phoneme is spelled with.
+///
+/// Silent letters join the phoneme before them, or the first phoneme at the start of a word.
+/// A letter spelling two phonemes goes with the second, and the first is spelled with nothing:
+/// the l of -le spells /l/ rather than the vowel before it.
+fn spell_phonemes(letters: &[char], chunks: &[Vec<Phoneme>]) -> Vec<String> {
+    let mut spelled: Vec<String> = Vec::new();
+    let mut leading = String::new();
+    for (letter, chunk) in letters.iter().zip(chunks) {
+        match (chunk.len(), spelled.last_mut()) {
+            (0, Some(last)) => last.push(*letter),
+            (0, None) => leading.push(*letter),
+            (size, _) => {
+                spelled.extend((1..size).map(|_| String::new()));
+                spelled.push(letter.to_string());
+            },
+        }
+    }
+    if let Some(first) = spelled.first_mut() {
+        first.insert_str(0, &leading);
+    }
+    spelled
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::syllable::Syllable;
+
+    use super::*;
+
+    fn g2p() -> G2p {
+        let words = [
+            ("box", "B AA1 K S"), ("fox", "F AA1 K S"), ("ox", "AA1 K S"), ("tax", "T AE1 K S"), ("wax", "W AE1 K S"),
+            ("six", "S IH1 K S"), ("fix", "F IH1 K S"), ("mix", "M IH1 K S"), ("cat", "K AE1 T"), ("cap", "K AE1 P"),
+            ("tap", "T AE1 P"), ("bat", "B AE1 T"), ("sit", "S IH1 T"), ("bit", "B IH1 T"), ("pit", "P IH1 T"),
+            ("tip", "T IH1 P"), ("top", "T AA1 P"), ("pot", "P AA1 T"), ("mop", "M AA1 P"), ("one", "W AH1 N"),
+        ];
+        G2p::train(&SyllablizedPhonemes {
+            words: words.iter().map(|(spelling, arpabet)| (spelling.to_string(), vec![Syllable::from_arpabet(arpabet).unwrap()])).collect(),
+            alternates: vec![],
+            frequencies: HashMap::new(),
+        })
+    }
+
+    fn phonemes(arpabet: &str) -> Vec<Phoneme> {
+        arpabet.split_whitespace().map(|symbol| Phoneme::try_from_arpabet(symbol).unwrap()).collect()
+    }
+
+    #[test]
+    fn real_words_are_looked_up_and_others_predicted() {
+        let g2p = g2p();
+        assert_eq!(g2p.lookup("one").unwrap().phonemes(), phonemes("W AH N"));
+        assert_eq!(g2p.pronounce("Cat").unwrap().phonemes(), phonemes("K AE T"));
+        assert!(g2p.lookup("bip").is_none());
+        assert_eq!(g2p.pronounce("bip").unwrap().phonemes(), phonemes("B IH P"));
+    }
+
+    #[test]
+    fn x_is_aligned_to_two_phonemes() {
+        let g2p = g2p();
+        assert_eq!(g2p.predict("pax").unwrap().phonemes(), phonemes("P AE K S"));
+        assert_eq!(g2p.predict_spelling(&Word::syllabify(&phonemes("T AA K S"))), "tox");
+    }
+
+    #[test]
+    fn spellings_and_pronunciations_round_trip() {
+        let g2p = g2p();
+        for word in ["box", "six", "cat", "one", "mop"] {
+            assert_eq!(g2p.spell(&g2p.pronounce(word).unwrap()), word);
+        }
+        for spelling in ["bip", "tox", "pix"] {
+            assert_eq!(g2p.spell(&g2p.pronounce(spelling).unwrap()), spelling);
+        }
+    }
+
+    #[test]
+    fn unseen_letters_cant_be_predicted() {
+        let g2p = g2p();
+        assert!(g2p.predict("zig").is_none());
+        assert!(g2p.predict("").is_none());
+        assert!(g2p.pronounce("vex").is_none());
+    }
+}
@@ -1,10 +1,10 @@
-use std::{collections::{hash_map::Entry, HashMap}, env, fs::{self, File}, io::Write, path::Path};
+use std::{collections::{hash_map::Entry, HashMap, HashSet}, fs::{self, File}, io::Write, path::Path};
 
 use indicatif::ProgressIterator;
 use rand::rngs::ThreadRng;
 use serde::{Deserialize, Serialize};
 
-use crate::{logger::{ProgressBarElements, TerminalLogger, WorkIndex, WorkMessage}, phoneme::{Phoneme, SyllablePart}, syllable::Syllable, syllablize::SyllablizedPhonemes, utils};
+use crate::{dot::{self, DotConfig}, inventory::Inventory, logger::{ProgressBarElements, TerminalLogger, WorkIndex, WorkMessage}, phoneme::{Phoneme, SyllablePart}, prune::{PruneConfig, PruneReport, PrunedEdge}, syllable::Syllable, syllablize::SyllablizedPhonemes, utils};
 
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct NodeID {
@@ -58,10 +58,12 @@ pub struct SonorityGraph {
 }
 
 impl SonorityGraph {
-    const CACHE_FILE: &'static str = "assets/internal/sonority-graph.ron";
+    const CACHE_FILE: &'static str = "sonority-graph.ron";
+    /// The most edges tried while searching for a syllable that satisfies a filter. 
+    const BACKTRACK_BUDGET: usize = 1000;
 
     fn cache_file() -> String {
-        env::current_dir().unwrap().to_str().unwrap().to_owned() + "/" + Self::CACHE_FILE
+        Inventory::current().cache_file(Self::CACHE_FILE)
     }
     pub fn cache_exists() -> bool {
         Path::new(&Self::cache_file()).exists()
@@ -89,7 +91,8 @@ impl SonorityGraph {
             Entry::Occupied(_) => (),
         }
     }
-    pub fn add_edge(&mut self, from: NodeID, to: NodeID) {
+    /// Adds `count` to the edge between two existing nodes, creating it if needed. 
+    pub fn add_edge(&mut self, from: NodeID, to: NodeID, count: usize) {
         if self.get_node(to).is_none() { return }
 
         if let Some(from_node) = self.get_node_mut(from) {
@@ -97,11 +100,11 @@ impl SonorityGraph {
             for edge in from_node.outs.iter_mut() {
                 if edge.from == from && edge.to == to {
                     has_edge = true;
-                    edge.count += 1;
+                    edge.count += count;
                 }
             }
             if !has_edge {
-                from_node.outs.push(SonorityGraphEdge { from, to, count: 1 });
+                from_node.outs.push(SonorityGraphEdge { from, to, count });
             }
         }
     }
@@ -132,16 +135,7 @@ impl SonorityGraph {
 
         for (_word, syllables) in syl_phones.words.iter().progress_with(bar) {
             for syl in syllables.iter() {
-                let (onset, nucleus, coda) = syl.split().unwrap();
-                self.update_graph_part(SyllablePart::Onset, onset, match nucleus.get(0) {
-                    Some(phone) => NodeData::Phoneme(*phone),
-                    None => NodeData::Stop,
-                });
-                self.update_graph_part(SyllablePart::Nucleus, nucleus, match coda.get(0) {
-                    Some(phone) => NodeData::Phoneme(*phone),
-                    None => NodeData::Stop,
-                });
-                self.update_graph_part(SyllablePart::Coda { layer: 1 }, coda, NodeData::Stop);
+                self.add_syllable(syl, 1);
             }
         }
 
@@ -154,7 +148,21 @@ impl SonorityGraph {
 
         logger.finish_work(writing_work);
     }
-    fn update_graph_part(&mut self, part: SyllablePart, phonemes: Vec<Phoneme>, next: NodeData) {
+    /// Adds the path of `syllable` to the graph, as if it was seen `count` times. 
+    /// Syllables without a nucleus are skipped. 
+    pub fn add_syllable(&mut self, syllable: &Syllable, count: usize) {
+        let Some((onset, nucleus, coda)) = syllable.split() else { return };
+        self.update_graph_part(SyllablePart::Onset, onset, match nucleus.get(0) {
+            Some(phone) => NodeData::Phoneme(*phone),
+            None => NodeData::Stop,
+        }, count);
+        self.update_graph_part(SyllablePart::Nucleus, nucleus, match coda.get(0) {
+            Some(phone) => NodeData::Phoneme(*phone),
+            None => NodeData::Stop,
+        }, count);
+        self.update_graph_part(SyllablePart::Coda { layer: 1 }, coda, NodeData::Stop, count);
+    }
+    fn update_graph_part(&mut self, part: SyllablePart, phonemes: Vec<Phoneme>, next: NodeData, count: usize) {
         if part == SyllablePart::Onset {
             let from_node_id = NodeID { data: NodeData::Start, part };
             let to_node_id = match phonemes.get(0) {
@@ -163,7 +171,7 @@ impl SonorityGraph {
             };
             self.add_node(from_node_id);
             self.add_node(to_node_id);
-            self.add_edge(from_node_id, to_node_id);
+            self.add_edge(from_node_id, to_node_id, count);
         }
 
         let (mut cur_id, mut node_ids): (NodeID, Vec<NodeID>) = match (phonemes.get(0), part) {
@@ -198,15 +206,222 @@ impl SonorityGraph {
             let to_node_id = next_id;
             self.add_node(from_node_id);
             self.add_node(to_node_id);
-            self.add_edge(from_node_id, to_node_id);
+            self.add_edge(from_node_id, to_node_id, count);
             cur_id = next_id;
         }
     }
 
+    /// Returns every node that a syllable can begin from: the onset start node and 
+    /// every phoneme node in the onset or nucleus. 
+    pub fn root_ids(&self) -> Vec<NodeID> {
+        self.nodes
+            .keys()
+            .filter(|id| matches!(
+                (id.data, id.part),
+                (NodeData::Start, SyllablePart::Onset) | (NodeData::Phoneme(_), SyllablePart::Onset | SyllablePart::Nucleus)
+            ))
+            .copied()
+            .collect()
+    }
+
+    /// Returns every node reachable from one of `roots`, including the roots themselves. 
+    pub fn reachable_from(&self, roots: &[NodeID]) -> HashSet<NodeID> {
+        let mut reached = HashSet::new();
+        let mut stack: Vec<NodeID> = roots.iter().filter(|id| self.nodes.contains_key(id)).copied().collect();
+
+        while let Some(id) = stack.pop() {
+            if !reached.insert(id) { continue };
+            if let Some(node) = self.get_node(id) {
+                for edge in node.outs.iter() {
+                    if self.nodes.contains_key(&edge.to) && !reached.contains(&edge.to) {
+                        stack.push(edge.to);
+                    }
+                }
+            }
+        }
+
+        reached
+    }
+
+    /// Returns every node that has a path to a stop node. 
+    pub fn nodes_reaching_stop(&self) -> HashSet<NodeID> {
+        let mut reaching: HashSet<NodeID> = self.nodes
+            .keys()
+            .filter(|id| id.data == NodeData::Stop)
+            .copied()
+            .collect();
+
+        loop {
+            let mut changed = false;
+            for (id, node) in self.nodes.iter() {
+                if reaching.contains(id) { continue };
+                if node.outs.iter().any(|edge| reaching.contains(&edge.to)) {
+                    reaching.insert(*id);
+                    changed = true;
+                }
+            }
+            if !changed { break };
+        }
+
+        reaching
+    }
+
+    /// Removes edges that fall below the thresholds in `config`. 
+    /// 
+    /// The most common edge out of each node is always kept, and edges are restored whenever 
+    /// removing them would leave a node without a path to a stop, so every syllable that 
+    /// could be generated before pruning can still be completed afterwards. 
+    pub fn prune(&mut self, config: &PruneConfig) -> PruneReport {
+        let mut report = PruneReport::new();
+        let mut removed: HashMap<NodeID, Vec<SonorityGraphEdge>> = HashMap::new();
+
+        for (id, node) in self.nodes.iter_mut() {
+            let total: usize = node.outs.iter().map(|edge| edge.count).sum();
+            let Some(strongest) = node.outs.iter().map(|edge| edge.count).max() else { continue };
+
+            let mut kept_strongest = false;
+            let mut kept = Vec::new();
+            for edge in node.outs.drain(..) {
+                if edge.count == strongest && !kept_strongest {
+                    kept_strongest = true;
+                    kept.push(edge);
+                } else if config.should_prune(edge.count, total) {
+                    removed.entry(*id).or_default().push(edge);
+                } else {
+                    kept.push(edge);
+                }
+            }
+            node.outs = kept;
+        }
+
+        loop {
+            let reaching = self.nodes_reaching_stop();
+            let stranded: Vec<NodeID> = removed
+                .keys()
+                .filter(|id| !reaching.contains(id))
+                .copied()
+                .collect();
+            if stranded.is_empty() { break };
+
+            for id in stranded {
+                let edges = removed.remove(&id).unwrap();
+                self.get_node_mut(id).unwrap().outs.extend(edges);
+            }
+        }
+
+        let reachable = self.reachable_from(&self.root_ids());
+        let unreachable: Vec<NodeID> = self.nodes
+            .keys()
+            .filter(|id| !reachable.contains(id))
+            .copied()
+            .collect();
+        for id in unreachable {
+            if let Some(node) = self.nodes.remove(&id) {
+                report.removed_edges.extend(node.outs.into_iter().map(PrunedEdge::Graph));
+            }
+            report.removed_nodes.push(id);
+        }
+
+        for (_id, edges) in removed {
+            report.removed_edges.extend(edges.into_iter().map(PrunedEdge::Graph));
+        }
+
+        report
+    }
+
+    /// Exports the graph to Graphviz DOT, with one cluster per syllable part. 
+    pub fn to_dot(&self, config: &DotConfig) -> String {
+        let part_order = |part: SyllablePart| match part {
+            SyllablePart::Onset => 0,
+            SyllablePart::Nucleus => 1,
+            SyllablePart::Coda { layer: 0 } => usize::MAX,
+            SyllablePart::Coda { layer } => layer + 1,
+        };
+
+        let mut ids: Vec<NodeID> = self.nodes.keys().copied().collect();
+        ids.sort_by_key(|id| (part_order(id.part), dot::data_name(id.data)));
+
+        let max = self.nodes
+            .values()
+            .flat_map(|node| node.outs.iter().map(|edge| edge.count))
+            .max()
+            .unwrap_or(1);
+
+        let mut res = String::from("digraph sonority_graph {\n    rankdir=LR;\n    node [shape=ellipse];\n");
+
+        let mut i = 0;
+        while i < ids.len() {
+            let part = ids[i].part;
+            res += &format!("    subgraph \"cluster_{}\" {{\n        label=\"{}\";\n", dot::node_name(NodeID { data: NodeData::Start, part }), dot::part_name(part));
+            while i < ids.len() && ids[i].part == part {
+                res += &format!("        \"{}\" [label=\"{}\"];\n", dot::node_name(ids[i]), dot::data_label(ids[i].data));
+                i += 1;
+            }
+            res += "    }\n";
+        }
+
+        for id in ids.iter() {
+            let node = self.get_node_unchecked(*id);
+            let total: usize = node.outs.iter().map(|edge| edge.count).sum();
+            for edge in node.outs.iter() {
+                if config.threshold.should_prune(edge.count, total) { continue };
+                res += &format!(
+                    "    \"{}\" -> \"{}\" [{}];\n",
+                    dot::node_name(edge.from),
+                    dot::node_name(edge.to),
+                    config.edge_attributes(edge.count, total, max)
+                );
+            }
+        }
+
+        res += "}\n";
+        res
+    }
+
+    /// Returns the node a syllable starting with `start` begins from. 
+    pub fn start_id(start: Phoneme) -> NodeID {
+        NodeID {
+            data: NodeData::Phoneme(start), 
+            part: match start.is_syllabic() {
+                true => SyllablePart::Nucleus,
+                false => SyllablePart::Onset,
+            }
+        }
+    }
+
+    /// Returns the log probability of generating `syllable` from the node its first phoneme 
+    /// begins at, and the number of choices that took. 
+    /// 
+    /// Returns none if the graph cannot generate `syllable`. 
+    pub fn log_probability(&self, syllable: &Syllable) -> Option<(f64, usize)> {
+        let (onset, nucleus, coda) = syllable.split()?;
+        let mut path: Vec<(NodeData, Option<SyllablePart>)> = onset.iter().map(|phone| (NodeData::Phoneme(*phone), Some(SyllablePart::Onset)))
+            .chain(nucleus.iter().map(|phone| (NodeData::Phoneme(*phone), Some(SyllablePart::Nucleus))))
+            .chain(coda.iter().map(|phone| (NodeData::Phoneme(*phone), Some(SyllablePart::Coda { layer: 1 }))))
+            .collect();
+        path.push((NodeData::Stop, None));
+
+        let mut cur_id = Self::start_id(syllable.first_phoneme());
+        self.get_node(cur_id)?;
+        let mut log_probability = 0.0;
+        for (data, part) in path.into_iter().skip(1) {
+            let node = self.get_node(cur_id)?;
+            let total: usize = node.outs.iter().map(|edge| edge.count).sum();
+            let edge = node.outs.iter().find(|edge| {
+                edge.to.data == data && part.is_none_or(|part| {
+                    (part.is_onset(), part.is_nucleus(), part.is_coda()) == (edge.to.part.is_onset(), edge.to.part.is_nucleus(), edge.to.part.is_coda())
+                })
+            })?;
+            log_probability += (edge.count as f64 / total as f64).ln();
+            cur_id = edge.to;
+        }
+        Some((log_probability, syllable.phonemes().len()))
+    }
+
     fn eval(&self, result: &mut SonorityGraphResult, cur_id: NodeID, mut rng: &mut ThreadRng) {
-        let cur_node = self.get_node_unchecked(cur_id);
+        let Some(cur_node) = self.get_node(cur_id) else { return };
 
-        let edge = utils::weighted_random_choice(&cur_node.outs.iter().map(|edge| (edge.count, edge.clone())).collect(), &mut rng);
+        let Some(edge) = utils::weighted_random_choice(&cur_node.outs.iter().map(|edge| (edge.count, edge.clone())).collect(), &mut rng) else { return };
         let Some(next_node) = self.get_node(edge.to) else { return };
         
         let should_continue = next_node.evaluate(result, edge.to);
@@ -219,18 +434,96 @@ impl SonorityGraph {
         self.eval(&mut result, root_id, rng);
         result
     }
-    pub fn evaluate_from_start(&self, start: Phoneme, rng: &mut ThreadRng) -> SonorityGraphResult {
+    /// Generates a syllable beginning with `start`. 
+    /// 
+    /// Returns none if the graph has no node to start from. 
+    pub fn evaluate_from_start(&self, start: Phoneme, rng: &mut ThreadRng) -> Option<SonorityGraphResult> {
         let mut result = SonorityGraphResult(Syllable::empty());
-        let root_id = NodeID {
-            data: NodeData::Phoneme(start), 
-            part: match start.is_vowel() {
-                true => SyllablePart::Nucleus,
-                false => SyllablePart::Onset,
-            }
-        };
-        let root_node = self.get_node_unchecked(root_id);
+        let root_id = Self::start_id(start);
+        let root_node = self.get_node(root_id)?;
         root_node.evaluate(&mut result, root_id);
         self.eval(&mut result, root_id, rng);
-        result
+        Some(result)
     }
-}
\ No newline at end of file
+
+    fn eval_with(&self, result: &mut SonorityGraphResult, cur_id: NodeID, rng: &mut ThreadRng, allow: &dyn Fn(&Syllable, NodeID) -> bool, budget: &mut usize) -> bool {
+        let Some(cur_node) = self.get_node(cur_id) else { return false };
+
+        let mut candidates: Vec<(usize, NodeID)> = cur_node.outs
+            .iter()
+            .filter(|edge| self.nodes.contains_key(&edge.to) && allow(&result.0, edge.to))
+            .map(|edge| (edge.count, edge.to))
+            .collect();
+
+        while *budget > 0 {
+            let Some(next_id) = utils::weighted_random_choice(&candidates, rng) else { return false };
+            candidates.retain(|candidate| candidate.1 != next_id);
+            *budget -= 1;
+
+            if !self.get_node_unchecked(next_id).evaluate(result, next_id) { return true };
+            if self.eval_with(result, next_id, rng, allow, budget) { return true };
+            result.0.pop_phoneme();
+        }
+        false
+    }
+    /// Generates a syllable beginning with `start`, only ever moving to nodes that `allow` accepts 
+    /// given the syllable so far. Choices that lead nowhere are backtracked. 
+    /// 
+    /// Returns none if no syllable satisfying `allow` was found. 
+    pub fn evaluate_from_start_with(&self, start: Phoneme, rng: &mut ThreadRng, allow: &dyn Fn(&Syllable, NodeID) -> bool) -> Option<SonorityGraphResult> {
+        let mut result = SonorityGraphResult(Syllable::empty());
+        let root_id = Self::start_id(start);
+        let root_node = self.get_node(root_id)?;
+        if !allow(&result.0, root_id) { return None };
+
+        root_node.evaluate(&mut result, root_id);
+        let mut budget = Self::BACKTRACK_BUDGET;
+        match self.eval_with(&mut result, root_id, rng, allow, &mut budget) {
+            true => Some(result),
+            false => None,
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn onset(phoneme: Phoneme) -> NodeID {
+        NodeID { data: NodeData::Phoneme(phoneme), part: SyllablePart::Onset }
+    }
+
+    #[test]
+    fn prune_restores_the_only_path_to_stop() {
+        let nucleus = NodeID { data: NodeData::Phoneme(Phoneme::AA), part: SyllablePart::Nucleus };
+        let stop = NodeID { data: NodeData::Stop, part: SyllablePart::Coda { layer: 0 } };
+        let mut graph = SonorityGraph { nodes: HashMap::new() };
+        for id in [onset(Phoneme::T), onset(Phoneme::S), nucleus, stop] {
+            graph.add_node(id);
+        }
+        // S is a dead end, so the rare edge to the nucleus is the only way T can finish a syllable
+        graph.add_edge(onset(Phoneme::T), onset(Phoneme::S), 10);
+        graph.add_edge(onset(Phoneme::T), nucleus, 1);
+        graph.add_edge(nucleus, stop, 1);
+
+        let report = graph.prune(&PruneConfig::default());
+        let outs: Vec<NodeID> = graph.get_node_unchecked(onset(Phoneme::T)).outs.iter().map(|edge| edge.to).collect();
+        assert_eq!(outs, [onset(Phoneme::S), nucleus]);
+        assert!(report.removed_edges.is_empty());
+        assert!(report.removed_nodes.is_empty());
+    }
+
+    #[test]
+    fn prune_removes_unreachable_nodes() {
+        let mut graph = SonorityGraph { nodes: HashMap::new() };
+        graph.add_syllable(&Syllable::from_arpabet("T AA N").unwrap(), 10);
+        graph.add_syllable(&Syllable::from_arpabet("T AA K").unwrap(), 1);
+
+        let k = NodeID { data: NodeData::Phoneme(Phoneme::K), part: SyllablePart::Coda { layer: 1 } };
+        let report = graph.prune(&PruneConfig::default());
+        assert!(!graph.nodes.contains_key(&k));
+        assert_eq!(report.removed_nodes, [k]);
+        assert_eq!(report.graph_edge_count(), 2);
+        assert!(graph.log_probability(&Syllable::from_arpabet("T AA N").unwrap()).is_some());
+        assert!(graph.log_probability(&Syllable::from_arpabet("T AA K").unwrap()).is_none());
+    }
+}
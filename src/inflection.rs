@@ -0,0 +1,208 @@
+use crate::{morphology::PartOfSpeech, phoneme::Phoneme, word::Word};
+
+/// The forms English nouns and verbs take in sentences.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Inflection {
+    Plural,
+    Possessive,
+    ThirdPerson,
+    Past,
+    Progressive,
+}
+
+impl Inflection {
+    pub const ALL: [Inflection; 5] = [Self::Plural, Self::Possessive, Self::ThirdPerson, Self::Past, Self::Progressive];
+    /// The phonemes the endings are made of, which the inventory in use has to define.
+    pub const PHONEMES: [Phoneme; 6] = [Phoneme::IH, Phoneme::Z, Phoneme::S, Phoneme::T, Phoneme::D, Phoneme::NG];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Plural => "plural",
+            Self::Possessive => "possessive",
+            Self::ThirdPerson => "third person",
+            Self::Past => "past",
+            Self::Progressive => "-ing",
+        }
+    }
+
+    /// The inflections words of `part_of_speech` take: plural and possessive for nouns, and
+    /// third person, past and -ing for verbs. Words of an unknown part of speech take them all.
+    pub fn of(part_of_speech: Option<PartOfSpeech>) -> &'static [Inflection] {
+        match part_of_speech {
+            Some(PartOfSpeech::Noun) => &Self::ALL[..2],
+            Some(PartOfSpeech::Verb) => &Self::ALL[2..],
+            Some(PartOfSpeech::Adjective | PartOfSpeech::Adverb) => &[],
+            None => &Self::ALL,
+        }
+    }
+
+    /// Returns the ending after a word ending in `last`: /ɪz/ after sibilants and /s/ after
+    /// other voiceless sounds for -s, /ɪd/ after /t/ and /d/ and /t/ after other voiceless
+    /// sounds for -ed, and /z/ and /d/ otherwise.
+    fn ending(&self, last: Option<Phoneme>) -> Vec<Phoneme> {
+        let voiceless = last.is_some_and(|phone| !phone.is_syllabic() && !phone.features().voiced);
+        match self {
+            Self::Plural | Self::Possessive | Self::ThirdPerson => match last {
+                Some(Phoneme::S | Phoneme::Z | Phoneme::SH | Phoneme::ZH | Phoneme::CH | Phoneme::JH) => vec![Phoneme::IH, Phoneme::Z],
+                _ if voiceless => vec![Phoneme::S],
+                _ => vec![Phoneme::Z],
+            },
+            Self::Past => match last {
+                Some(Phoneme::T | Phoneme::D) => vec![Phoneme::IH, Phoneme::D],
+                _ if voiceless => vec![Phoneme::T],
+                _ => vec![Phoneme::D],
+            },
+            Self::Progressive => vec![Phoneme::IH, Phoneme::NG],
+        }
+    }
+
+    /// Inflects `word`, syllabifying its last syllable again so a final consonant can start the
+    /// syllable of the ending. The other syllables and the stress stay as they were.
+    pub fn apply(&self, word: &Word) -> Word {
+        let Some((last, rest)) = word.syllables.split_last() else { return word.clone() };
+        let mut tail = last.phonemes().to_vec();
+        let boundary = tail.len();
+        tail.extend(self.ending(tail.last().copied()));
+
+        let mut inflected = Word::new(rest.to_vec());
+        for syllable in Word::syllabify_at(&tail, boundary).syllables {
+            inflected.add_syllable(syllable);
+        }
+        inflected.stress_syllable(word.stressed_syllable());
+        inflected
+    }
+
+    /// Spells the inflection of `word`, spelled `spelling`, the way English does: boxes, cries,
+    /// hoped, stopped, making, dying.
+    pub fn spell(&self, word: &Word, spelling: &str) -> String {
+        let last = word.phonemes().last().copied();
+        let stem = |suffix: char| spelling.strip_suffix(suffix).unwrap_or(spelling);
+        let consonant_y = spelling
+            .strip_suffix('y')
+            .and_then(|rest| rest.chars().last())
+            .is_some_and(|letter| !is_vowel_letter(letter));
+        let silent_e = spelling.ends_with('e') && last.is_some_and(|phone| !phone.is_syllabic());
+
+        match self {
+            Self::Possessive => format!("{}'s", spelling),
+            Self::Plural | Self::ThirdPerson if consonant_y => format!("{}ies", stem('y')),
+            Self::Plural | Self::ThirdPerson if self.ending(last).len() > 1 && !spelling.ends_with('e') => format!("{}es", spelling),
+            Self::Plural | Self::ThirdPerson => format!("{}s", spelling),
+            Self::Past if consonant_y => format!("{}ied", stem('y')),
+            Self::Past if spelling.ends_with('e') => format!("{}d", spelling),
+            Self::Past => format!("{}ed", doubled(word, spelling)),
+            Self::Progressive if spelling.ends_with("ie") => format!("{}ying", &spelling[..spelling.len() - 2]),
+            Self::Progressive if silent_e => format!("{}ing", stem('e')),
+            Self::Progressive => format!("{}ing", doubled(word, spelling)),
+        }
+    }
+}
+
+fn is_vowel_letter(letter: char) -> bool {
+    matches!(letter, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// Doubles the final consonant letter of a stressed final syllable spelled with a single vowel
+/// letter, like stop in stopped.
+fn doubled(word: &Word, spelling: &str) -> String {
+    let letters: Vec<char> = spelling.chars().collect();
+    let stressed_last = word.stressed_syllable() + 1 == word.syllables.len();
+    let double = match letters[..] {
+        [.., before, vowel, last] => {
+            stressed_last && !is_vowel_letter(before) && is_vowel_letter(vowel) && !is_vowel_letter(last) && !"wxy".contains(last) && last.is_alphabetic()
+        },
+        _ => false,
+    };
+    match (double, letters.last()) {
+        (true, Some(last)) => format!("{}{}", spelling, last),
+        _ => spelling.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syllable::{Stress, Syllable};
+
+    use super::*;
+
+    fn word(syllables: &[&str]) -> Word {
+        Word::new(syllables.iter().map(|arpabet| Syllable::from_arpabet(arpabet).unwrap()).collect())
+    }
+
+    fn syllables(word: &Word) -> Vec<String> {
+        word.syllables.iter().map(|syl| syl.to_arpabet().trim_end().to_owned()).collect()
+    }
+
+    #[test]
+    fn s_endings_follow_the_last_sound() {
+        assert_eq!(Inflection::Plural.ending(Some(Phoneme::T)), [Phoneme::S]);
+        assert_eq!(Inflection::Plural.ending(Some(Phoneme::G)), [Phoneme::Z]);
+        assert_eq!(Inflection::Plural.ending(Some(Phoneme::IY)), [Phoneme::Z]);
+        assert_eq!(Inflection::ThirdPerson.ending(Some(Phoneme::CH)), [Phoneme::IH, Phoneme::Z]);
+        assert_eq!(Inflection::Possessive.ending(Some(Phoneme::S)), [Phoneme::IH, Phoneme::Z]);
+
+        assert_eq!(syllables(&word(&["K AE1 T"]).inflect(Inflection::Plural)), ["K AE T S"]);
+        assert_eq!(syllables(&word(&["D AO1 G"]).inflect(Inflection::Plural)), ["D AO G Z"]);
+        assert_eq!(word(&["B AA1 K S"]).inflect(Inflection::Plural).phonemes(), [Phoneme::B, Phoneme::AA, Phoneme::K, Phoneme::S, Phoneme::IH, Phoneme::Z]);
+        assert_eq!(word(&["B AA1 K S"]).inflect(Inflection::Plural).syllables.len(), 2);
+    }
+
+    #[test]
+    fn past_endings_follow_the_last_sound() {
+        assert_eq!(Inflection::Past.ending(Some(Phoneme::P)), [Phoneme::T]);
+        assert_eq!(Inflection::Past.ending(Some(Phoneme::B)), [Phoneme::D]);
+        assert_eq!(Inflection::Past.ending(Some(Phoneme::D)), [Phoneme::IH, Phoneme::D]);
+
+        assert_eq!(syllables(&word(&["HH OW1 P"]).inflect(Inflection::Past)), ["H OW P T"]);
+        assert_eq!(syllables(&word(&["V IH1", "Z IH0 T"]).inflect(Inflection::Past)), ["V IH", "Z IH", "T IH D"]);
+    }
+
+    #[test]
+    fn inflections_keep_the_stress() {
+        let visited = word(&["V IH1", "Z IH0 T"]).inflect(Inflection::Past);
+        assert_eq!(visited.syllables.iter().map(|syl| syl.stress()).collect::<Vec<Stress>>(), [Stress::Primary, Stress::Unstressed, Stress::Unstressed]);
+    }
+
+    #[test]
+    fn endings_starting_with_a_vowel_are_a_new_syllable() {
+        assert_eq!(syllables(&word(&["S IY1"]).inflect(Inflection::Progressive)), ["S IY", "IH NG"]);
+        assert_eq!(syllables(&word(&["M EY1 K"]).inflect(Inflection::Progressive)), ["M EY", "K IH NG"]);
+    }
+
+    #[test]
+    fn spells_s_endings() {
+        assert_eq!(Inflection::Plural.spell(&word(&["B AA1 K S"]), "box"), "boxes");
+        assert_eq!(Inflection::Plural.spell(&word(&["K AE1 T"]), "cat"), "cats");
+        assert_eq!(Inflection::Plural.spell(&word(&["R OW1 Z"]), "rose"), "roses");
+        assert_eq!(Inflection::ThirdPerson.spell(&word(&["K R AY1"]), "cry"), "cries");
+        assert_eq!(Inflection::ThirdPerson.spell(&word(&["P L EY1"]), "play"), "plays");
+        assert_eq!(Inflection::Possessive.spell(&word(&["B AA1 K S"]), "box"), "box's");
+    }
+
+    #[test]
+    fn spells_past_endings() {
+        assert_eq!(Inflection::Past.spell(&word(&["HH OW1 P"]), "hope"), "hoped");
+        assert_eq!(Inflection::Past.spell(&word(&["S T AA1 P"]), "stop"), "stopped");
+        assert_eq!(Inflection::Past.spell(&word(&["K R AY1"]), "cry"), "cried");
+        assert_eq!(Inflection::Past.spell(&word(&["P L EY1"]), "play"), "played");
+        assert_eq!(Inflection::Past.spell(&word(&["V IH1", "Z IH0 T"]), "visit"), "visited");
+        assert_eq!(Inflection::Past.spell(&word(&["F IH1 K S"]), "fix"), "fixed");
+    }
+
+    #[test]
+    fn spells_ing_endings() {
+        assert_eq!(Inflection::Progressive.spell(&word(&["M EY1 K"]), "make"), "making");
+        assert_eq!(Inflection::Progressive.spell(&word(&["D AY1"]), "die"), "dying");
+        assert_eq!(Inflection::Progressive.spell(&word(&["S T AA1 P"]), "stop"), "stopping");
+        assert_eq!(Inflection::Progressive.spell(&word(&["S IY1"]), "see"), "seeing");
+    }
+
+    #[test]
+    fn spells_short_words() {
+        assert_eq!(Inflection::Plural.spell(&word(&["EY1"]), "a"), "as");
+        assert_eq!(Inflection::Past.spell(&word(&["AA1 D"]), "od"), "oded");
+        assert_eq!(Inflection::Progressive.spell(&word(&["G OW1"]), "go"), "going");
+        assert_eq!(Inflection::Plural.spell(&Word::empty(), ""), "s");
+        assert_eq!(Inflection::Progressive.spell(&Word::empty(), ""), "ing");
+    }
+}
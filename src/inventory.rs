@@ -0,0 +1,282 @@
+use core::fmt;
+use std::{cmp::Reverse, collections::HashMap, env, fs, io, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::features::PhonemeFeatures;
+
+const ENGLISH: &str = include_str!("../assets/inventories/english.ron");
+
+static CURRENT: OnceLock<Inventory> = OnceLock::new();
+
+
+#[derive(Debug)]
+pub enum InventoryFileError {
+    Io(io::Error),
+    Parse(Box<ron::error::SpannedError>),
+    /// Two phonemes share a symbol, alias or IPA transcription.
+    Duplicate(String),
+    /// A symbol is empty, contains whitespace or a period, or ends with a stress digit.
+    InvalidSymbol(String),
+    /// No phoneme can be the nucleus of a syllable.
+    NoSyllabicPhonemes,
+    /// A diphthong isn't two or more syllabic phonemes of the inventory.
+    InvalidDiphthong(String),
+}
+
+impl fmt::Display for InventoryFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the inventory file: {}", err),
+            Self::Parse(err) => write!(f, "could not parse the inventory file: {}", err),
+            Self::Duplicate(symbol) => write!(f, "{} is defined more than once", symbol),
+            Self::InvalidSymbol(symbol) => write!(f, "{:?} cannot be used as a phoneme symbol", symbol),
+            Self::NoSyllabicPhonemes => write!(f, "no phoneme in the inventory is syllabic"),
+            Self::InvalidDiphthong(diphthong) => write!(f, "{:?} is not a sequence of syllabic phonemes of the inventory", diphthong),
+        }
+    }
+}
+
+impl std::error::Error for InventoryFileError {}
+
+
+/// A phoneme as written in an inventory file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PhonemeDefinition {
+    /// How the phoneme is written in the lexicon.
+    pub symbol: String,
+    /// Other ways the phoneme may be written in the lexicon.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub ipa: String,
+    /// How the phoneme is spelled in the respelling of a word.
+    pub respelling: String,
+    /// Vowels have a manner of `Vowel`, everything else is a consonant.
+    pub features: PhonemeFeatures,
+}
+
+
+/// The phonemes of a language, the lexicon to train on, and how to respell words.
+///
+/// Inventories are read from RON files; `assets/inventories/english.ron` is the default and
+/// documents the format.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Inventory {
+    /// Models trained on inventories other than English are cached in `assets/internal/<name>`.
+    pub name: String,
+    /// A syllabified lexicon with a word and its pronunciation on each line, separated by two
+    /// spaces. Phonemes are separated by spaces and syllables by periods, and digits after a
+    /// symbol are ignored. Inventories only used by a phonology file don't need one.
+    #[serde(default)]
+    pub lexicon: Option<String>,
+    /// A file listing words from most to least frequent, one per line. Only the first column of
+    /// tab separated lines is read.
+    #[serde(default)]
+    pub word_frequency: Option<String>,
+    pub phonemes: Vec<PhonemeDefinition>,
+    /// Respellings of IPA sequences as `(respelling, ipa)`, tried in order before the
+    /// respellings of single phonemes.
+    #[serde(default)]
+    pub respellings: Vec<(String, String)>,
+    /// Respellings used when all that is left of a syllable is the IPA, as `(respelling, ipa)`.
+    #[serde(default)]
+    pub final_respellings: Vec<(String, String)>,
+    /// Sequences of syllabic phonemes that make up one nucleus, written as space separated
+    /// symbols like `"a i"`. Any other syllabic phonemes next to each other are in separate
+    /// syllables; phonemes that are diphthongs on their own, like English `AY`, aren't listed.
+    #[serde(default)]
+    pub diphthongs: Vec<String>,
+
+    #[serde(skip)]
+    symbols: HashMap<String, usize>,
+    #[serde(skip)]
+    ipas: HashMap<String, usize>,
+    /// `respellings` followed by the respellings of single phonemes.
+    #[serde(skip)]
+    respell_key: Vec<(String, String)>,
+    /// `diphthongs` split into the symbols of their phonemes.
+    #[serde(skip)]
+    diphthong_symbols: Vec<Vec<String>>,
+}
+
+impl Inventory {
+    /// The English inventory the generator uses by default.
+    pub fn english() -> Self {
+        Self::parse(ENGLISH).expect("The English inventory is invalid")
+    }
+
+    pub fn load(path: &str) -> Result<Self, InventoryFileError> {
+        let contents = fs::read_to_string(path).map_err(InventoryFileError::Io)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, InventoryFileError> {
+        let mut inventory: Inventory = ron::de::from_str(contents).map_err(|err| InventoryFileError::Parse(Box::new(err)))?;
+
+        for (i, phoneme) in inventory.phonemes.iter().enumerate() {
+            for symbol in [&phoneme.symbol].into_iter().chain(phoneme.aliases.iter()) {
+                let invalid = symbol.is_empty()
+                    || symbol.contains(|c: char| c.is_whitespace() || c == '.')
+                    || symbol.ends_with(|c: char| c.is_ascii_digit());
+                if invalid {
+                    return Err(InventoryFileError::InvalidSymbol(symbol.clone()));
+                }
+                if inventory.symbols.insert(symbol.clone(), i).is_some() {
+                    return Err(InventoryFileError::Duplicate(symbol.clone()));
+                }
+            }
+            if inventory.ipas.insert(phoneme.ipa.clone(), i).is_some() {
+                return Err(InventoryFileError::Duplicate(format!("/{}/", phoneme.ipa)));
+            }
+        }
+
+        if !inventory.phonemes.iter().any(|phoneme| phoneme.features.syllabic) {
+            return Err(InventoryFileError::NoSyllabicPhonemes);
+        }
+
+        for diphthong in inventory.diphthongs.iter() {
+            let symbols: Option<Vec<String>> = diphthong
+                .split_whitespace()
+                .map(|symbol| inventory.get(symbol).filter(|phoneme| phoneme.features.syllabic).map(|phoneme| phoneme.symbol.clone()))
+                .collect();
+            match symbols {
+                Some(symbols) if symbols.len() > 1 => inventory.diphthong_symbols.push(symbols),
+                _ => return Err(InventoryFileError::InvalidDiphthong(diphthong.clone())),
+            }
+        }
+
+        let mut singles: Vec<(String, String)> = inventory.phonemes
+            .iter()
+            .map(|phoneme| (phoneme.respelling.clone(), phoneme.ipa.clone()))
+            .collect();
+        // longer transcriptions first, so /tʃ/ is not read as /t/ followed by /ʃ/
+        singles.sort_by_key(|(_, ipa)| Reverse(ipa.len()));
+        inventory.respell_key = inventory.respellings.iter().cloned().chain(singles).collect();
+
+        Ok(inventory)
+    }
+
+    /// Makes this the inventory used by every phoneme.
+    ///
+    /// Returns none if an inventory is already in use, which happens once any phoneme was created.
+    pub fn install(self) -> Option<()> {
+        CURRENT.set(self).ok()
+    }
+
+    /// Returns the inventory in use, which is English unless another one was installed.
+    pub fn current() -> &'static Self {
+        CURRENT.get_or_init(Self::english)
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&PhonemeDefinition> {
+        self.symbols.get(symbol).map(|i| &self.phonemes[*i])
+    }
+    pub fn get_by_ipa(&self, ipa: &str) -> Option<&PhonemeDefinition> {
+        self.ipas.get(ipa).map(|i| &self.phonemes[*i])
+    }
+
+    /// Returns true if the phonemes with `symbols` are one of the inventory's diphthongs or the
+    /// start of one.
+    pub fn starts_diphthong(&self, symbols: &[&str]) -> bool {
+        self.diphthong_symbols
+            .iter()
+            .any(|diphthong| diphthong.len() >= symbols.len() && diphthong.iter().zip(symbols).all(|(a, b)| a == b))
+    }
+
+    /// Returns the path of a cache file for models trained on this inventory, creating its
+    /// directory if needed.
+    pub fn cache_file(&self, file: &str) -> String {
+        let mut dir = env::current_dir().unwrap().to_str().unwrap().to_owned() + "/assets/internal";
+        if self.name != "english" {
+            dir += "/";
+            dir += &self.name;
+        }
+        let _ = fs::create_dir_all(&dir);
+        dir + "/" + file
+    }
+
+    /// Spells out an IPA transcription of a syllable.
+    ///
+    /// Characters that cannot be respelled are kept as they are.
+    pub fn respell_ipa(&self, ipa: &str) -> String {
+        self.respell_ipa_with(ipa, &[])
+    }
+
+    /// Spells out an IPA transcription of a syllable, trying the `(respelling, ipa)` pairs of
+    /// `overrides` in order before the inventory's own respellings.
+    pub fn respell_ipa_with(&self, ipa: &str, overrides: &[(&str, &str)]) -> String {
+        self.respell_segments(ipa, overrides, true).into_iter().map(|(_, respelled)| respelled).collect()
+    }
+
+    /// Splits an IPA transcription of a syllable into the pieces it is respelled in, each paired
+    /// with its respelling. If `finals` is set, the end of the syllable is respelled by the
+    /// inventory's final respellings if one matches, before `overrides`.
+    pub fn respell_segments<'a>(&self, ipa: &'a str, overrides: &[(&str, &str)], finals: bool) -> Vec<(&'a str, String)> {
+        let mut segments = Vec::new();
+        let mut ipa = ipa;
+
+        while let Some(first) = ipa.chars().next() {
+            if let Some((replace, _)) = self.final_respellings.iter().find(|(_, end)| finals && end == ipa) {
+                segments.push((ipa, replace.clone()));
+                break;
+            }
+
+            let found = overrides
+                .iter()
+                .copied()
+                .chain(self.respell_key.iter().map(|(replace, check)| (replace.as_str(), check.as_str())))
+                .find(|(_, check)| !check.is_empty() && ipa.starts_with(check));
+            let len = match found {
+                Some((replace, check)) => {
+                    segments.push((&ipa[..check.len()], replace.to_owned()));
+                    check.len()
+                },
+                None => {
+                    segments.push((&ipa[..first.len_utf8()], first.to_string()));
+                    first.len_utf8()
+                },
+            };
+            ipa = &ipa[len..];
+        }
+
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diphthongs_are_matched_by_their_start() {
+        let inventory = Inventory::parse(r#"(
+            name: "test",
+            phonemes: [
+                (symbol: "a", ipa: "a", respelling: "ah", features: (manner: Vowel, voiced: true, syllabic: true)),
+                (symbol: "i", ipa: "i", respelling: "ee", features: (manner: Vowel, voiced: true, syllabic: true)),
+                (symbol: "t", ipa: "t", respelling: "t", features: (manner: Stop)),
+            ],
+            diphthongs: ["a i"],
+        )"#).unwrap();
+        assert!(inventory.starts_diphthong(&["a"]));
+        assert!(inventory.starts_diphthong(&["a", "i"]));
+        assert!(!inventory.starts_diphthong(&["i", "a"]));
+        assert!(!inventory.starts_diphthong(&["a", "i", "a"]));
+    }
+
+    #[test]
+    fn diphthongs_must_be_syllabic_phonemes() {
+        let inventory = |diphthong: &str| Inventory::parse(&format!(r#"(
+            name: "test",
+            phonemes: [
+                (symbol: "a", ipa: "a", respelling: "ah", features: (manner: Vowel, voiced: true, syllabic: true)),
+                (symbol: "t", ipa: "t", respelling: "t", features: (manner: Stop)),
+            ],
+            diphthongs: [{:?}],
+        )"#, diphthong));
+        assert!(inventory("a a").is_ok());
+        assert!(inventory("a t").is_err());
+        assert!(inventory("a").is_err());
+        assert!(inventory("a o").is_err());
+    }
+}
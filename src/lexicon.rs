@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use crate::{phoneme::Phoneme, syllablize::SyllablizedPhonemes, word::Word};
+
+/// A pronunciation of a real word.
+#[derive(Clone)]
+pub struct LexiconEntry {
+    pub spelling: String,
+    pub word: Word,
+    /// 0 for the main pronunciation, 1 for the first alternate (`WORD(2)` in the lexicon) and so on.
+    pub variant: usize,
+    /// Position of the spelling in the frequency list, 0 being the most common word.
+    pub rank: usize,
+    /// How often the spelling appears in the word frequency list, or 0 if it isn't in it.
+    pub frequency: usize,
+}
+
+impl LexiconEntry {
+    /// The log of the frequency of the word, 0 for words missing from the frequency list.
+    pub fn log_frequency(&self) -> f64 {
+        (self.frequency.max(1) as f64).log10()
+    }
+}
+
+/// The words of the lexicon, indexed by spelling and by pronunciation.
+///
+/// Pronunciations are compared by their phonemes only, so the same sounds split into syllables
+/// differently still count as homophones.
+pub struct Lexicon {
+    entries: Vec<LexiconEntry>,
+    by_spelling: HashMap<String, Vec<usize>>,
+    by_pronunciation: HashMap<Vec<Phoneme>, Vec<usize>>,
+    /// Entries sorted by their phonemes, for prefix queries.
+    forward: Vec<(Vec<&'static str>, usize)>,
+    /// Entries sorted by their phonemes read backwards, for suffix queries.
+    backward: Vec<(Vec<&'static str>, usize)>,
+}
+
+fn key<'a>(phonemes: impl Iterator<Item = &'a Phoneme>) -> Vec<&'static str> {
+    phonemes.map(|phoneme| phoneme.to_arpabet()).collect()
+}
+
+impl Lexicon {
+    pub fn new(syl_phones: &SyllablizedPhonemes) -> Self {
+        let mut lexicon = Self {
+            entries: Vec::new(),
+            by_spelling: HashMap::new(),
+            by_pronunciation: HashMap::new(),
+            forward: Vec::new(),
+            backward: Vec::new(),
+        };
+
+        for (rank, (spelling, syllables)) in syl_phones.words.iter().enumerate() {
+            let frequency = syl_phones.frequencies.get(spelling).copied().unwrap_or(0);
+            lexicon.add(LexiconEntry { spelling: spelling.clone(), word: Word::new(syllables.clone()), variant: 0, rank, frequency });
+        }
+        for (spelling, syllables) in syl_phones.alternates.iter() {
+            // alternates of words that were left out of the lexicon have nothing to be an alternate of
+            let Some(main) = lexicon.by_spelling.get(spelling).and_then(|indices| indices.first()) else { continue };
+            let LexiconEntry { rank, frequency, .. } = lexicon.entries[*main];
+            let variant = lexicon.by_spelling[spelling].len();
+            lexicon.add(LexiconEntry { spelling: spelling.clone(), word: Word::new(syllables.clone()), variant, rank, frequency });
+        }
+
+        lexicon.forward.sort();
+        lexicon.backward.sort();
+        lexicon
+    }
+
+    fn add(&mut self, entry: LexiconEntry) {
+        let index = self.entries.len();
+        let phonemes = entry.word.phonemes();
+        self.by_spelling.entry(entry.spelling.clone()).or_default().push(index);
+        self.forward.push((key(phonemes.iter()), index));
+        self.backward.push((key(phonemes.iter().rev()), index));
+        self.by_pronunciation.entry(phonemes).or_default().push(index);
+        self.entries.push(entry);
+    }
+
+    /// Every pronunciation, most common words first.
+    pub fn entries(&self) -> &[LexiconEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn get(&self, indices: Option<&Vec<usize>>) -> Vec<&LexiconEntry> {
+        indices.into_iter().flatten().map(|i| &self.entries[*i]).collect()
+    }
+
+    /// Returns every pronunciation of a spelling, the main one first.
+    pub fn lookup(&self, spelling: &str) -> Vec<&LexiconEntry> {
+        self.get(self.by_spelling.get(&spelling.to_lowercase()))
+    }
+
+    /// Returns every word pronounced `word`, most common first. The word itself is included if
+    /// it is real.
+    pub fn homophones(&self, word: &Word) -> Vec<&LexiconEntry> {
+        let mut entries = self.get(self.by_pronunciation.get(&word.phonemes()));
+        entries.sort_by_key(|entry| (entry.rank, entry.variant));
+        entries
+    }
+
+    /// Returns whether some word is pronounced `word`.
+    pub fn contains(&self, word: &Word) -> bool {
+        self.by_pronunciation.contains_key(&word.phonemes())
+    }
+
+    /// Returns every pronunciation starting with `phonemes`, most common first.
+    pub fn with_prefix(&self, phonemes: &[Phoneme]) -> Vec<&LexiconEntry> {
+        Self::matching(&self.entries, &self.forward, &key(phonemes.iter()))
+    }
+
+    /// Returns every pronunciation ending with `phonemes`, most common first.
+    pub fn with_suffix(&self, phonemes: &[Phoneme]) -> Vec<&LexiconEntry> {
+        Self::matching(&self.entries, &self.backward, &key(phonemes.iter().rev()))
+    }
+
+    fn matching<'a>(entries: &'a [LexiconEntry], sorted: &[(Vec<&'static str>, usize)], start: &[&'static str]) -> Vec<&'a LexiconEntry> {
+        let first = sorted.partition_point(|(phonemes, _)| phonemes.as_slice() < start);
+        let mut matches: Vec<&LexiconEntry> = sorted[first..]
+            .iter()
+            .take_while(|(phonemes, _)| phonemes.starts_with(start))
+            .map(|(_, i)| &entries[*i])
+            .collect();
+        matches.sort_by_key(|entry| (entry.rank, entry.variant));
+        matches
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::syllable::Syllable;
+
+    use super::*;
+
+    fn syllables(arpabet: &str) -> Vec<Syllable> {
+        arpabet.split('.').map(|syllable| Syllable::from_arpabet(syllable).unwrap()).collect()
+    }
+
+    fn lexicon() -> Lexicon {
+        let words = [("cat", "K AE1 T"), ("cap", "K AE1 P"), ("scat", "S K AE1 T"), ("catnip", "K AE1 T . N IH0 P"), ("kat", "K AE1 . T")];
+        Lexicon::new(&SyllablizedPhonemes {
+            words: words.iter().map(|(spelling, arpabet)| (spelling.to_string(), syllables(arpabet))).collect(),
+            alternates: vec![(String::from("cat"), syllables("K AA1 T")), (String::from("dog"), syllables("D AO1 G"))],
+            frequencies: HashMap::new(),
+        })
+    }
+
+    fn spellings(entries: Vec<&LexiconEntry>) -> Vec<&str> {
+        entries.into_iter().map(|entry| entry.spelling.as_str()).collect()
+    }
+
+    #[test]
+    fn prefix_queries() {
+        let lexicon = lexicon();
+        assert_eq!(spellings(lexicon.with_prefix(&[Phoneme::K, Phoneme::AE])), ["cat", "cap", "catnip", "kat"]);
+        assert_eq!(spellings(lexicon.with_prefix(&[Phoneme::K, Phoneme::AE, Phoneme::T])), ["cat", "catnip", "kat"]);
+        assert_eq!(spellings(lexicon.with_prefix(&[Phoneme::K, Phoneme::AA])), ["cat"]);
+        assert_eq!(lexicon.with_prefix(&[]).len(), lexicon.len());
+        assert!(lexicon.with_prefix(&[Phoneme::K, Phoneme::AE, Phoneme::T, Phoneme::N, Phoneme::IH, Phoneme::P, Phoneme::S]).is_empty());
+        assert!(lexicon.with_prefix(&[Phoneme::Z]).is_empty());
+    }
+
+    #[test]
+    fn suffix_queries() {
+        let lexicon = lexicon();
+        assert_eq!(spellings(lexicon.with_suffix(&[Phoneme::AE, Phoneme::T])), ["cat", "scat", "kat"]);
+        assert_eq!(spellings(lexicon.with_suffix(&[Phoneme::T])), ["cat", "cat", "scat", "kat"]);
+        assert!(lexicon.with_suffix(&[Phoneme::S, Phoneme::K, Phoneme::AE, Phoneme::T, Phoneme::T]).is_empty());
+    }
+
+    #[test]
+    fn lookups_and_homophones() {
+        let lexicon = lexicon();
+        let cat = lexicon.lookup("CAT");
+        assert_eq!(cat.iter().map(|entry| entry.variant).collect::<Vec<_>>(), [0, 1]);
+        // alternates of words missing from the lexicon are left out
+        assert!(lexicon.lookup("dog").is_empty());
+        // syllabified differently, but pronounced the same
+        assert_eq!(spellings(lexicon.homophones(&cat[0].word)), ["cat", "kat"]);
+        assert!(!lexicon.contains(&Word::new(syllables("K AE1"))));
+    }
+}
@@ -1,4 +1,7 @@
 pub mod phoneme;
+pub mod features;
+pub mod inventory;
+pub mod filter;
 pub mod syllable;
 pub mod syllablize;
 pub mod graph;
@@ -6,22 +9,372 @@ pub mod wordgen;
 pub mod connections;
 pub mod word;
 pub mod utils;
+pub mod prune;
+pub mod validate;
+pub mod dot;
+pub mod stats;
+pub mod sonority;
+pub mod phonology;
+pub mod soundchange;
+pub mod narrow;
+pub mod dialect;
+pub mod notation;
+pub mod respell;
+pub mod g2p;
+pub mod lexicon;
+pub mod neighbors;
+pub mod metrics;
+pub mod pseudowords;
+pub mod rhyme;
+pub mod batch;
+pub mod blend;
+pub mod morphology;
+pub mod inflection;
 
 pub mod logger;
+pub mod cli;
 
+use std::{env, process};
+
+use cli::{Cli, Command};
+use g2p::G2p;
+use inflection::Inflection;
+use lexicon::Lexicon;
+use logger::TerminalLogger;
+use morphology::{Affix, AffixInventory, AffixPosition};
+use prune::PruneConfig;
+use rhyme::Rhyme;
+use soundchange::SoundChanges;
+use stats::{ModelStats, StatsConfig};
+use syllablize::LexiconError;
 use tts_rust::tts::GTTSClient;
+use word::Word;
 use wordgen::{FakeWordGenerator, WordGenConfig};
 
 
+fn load_sound_changes(file: &str) -> SoundChanges {
+    match SoundChanges::load(file) {
+        Ok(changes) => changes,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
+
+/// Exits with the error if the training words couldn't be loaded.
+fn check_loaded<T>(loaded: Result<T, LexiconError>) -> T {
+    match loaded {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
+
 fn main() {
-    let config = WordGenConfig::default();
-    let mut generator = FakeWordGenerator::new(config);
-    
-    let tts = GTTSClient::default();
-
-    for _ in 0..50 {
-        let res = generator.generate_word();
-        println!("{}", res);
-        tts.speak(&res.to_english().clone()).unwrap();
+    let cli = match Cli::parse(env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            process::exit(1);
+        }
+    };
+
+    // transcribing doesn't need a trained model
+    if let Command::Transcribe { words, output } = &cli.command {
+        for word in words {
+            match Word::from_xsampa(word) {
+                Some(parsed) => println!("{}: {}", word, output.format(&parsed)),
+                None => println!("{}: not a transcription of phonemes in the inventory", word),
+            }
+        }
+        return;
+    }
+
+    let mut config = WordGenConfig { inventory: cli.inventory, ..Default::default() };
+    if let Command::Generate { enforce_sonority, min_distance, prune, batch, .. } = &cli.command {
+        config.enforce_sonority = *enforce_sonority;
+        config.min_distance = *min_distance;
+        config.prune = prune.map(|min_count| PruneConfig { min_count, ..Default::default() });
+        config.batch = batch.clone();
+    }
+    let generator = match &cli.phonology {
+        Some(phonology) => FakeWordGenerator::from_phonology(phonology, config).map_err(|err| err.to_string()),
+        None => FakeWordGenerator::new(config).map_err(|err| err.to_string()),
+    };
+    let mut generator = match generator {
+        Ok(generator) => generator,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    match cli.command {
+        Command::Generate { count, speak, sound_changes, narrow, nearest, metrics, rhyme, rhyme_kind, affixes, part_of_speech, inflect, output, .. } => {
+            let tts = GTTSClient::default();
+            let sound_changes = sound_changes.map(|file| load_sound_changes(&file));
+            let rhyme = rhyme.map(|word| {
+                // a transcription between slashes doesn't need the dictionary
+                let rhymed = match word.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+                    Some(xsampa) => Word::from_xsampa(xsampa),
+                    None => {
+                        check_loaded(generator.load_syllablized_phonemes());
+                        Lexicon::new(&generator.syllablized_phonemes)
+                            .lookup(&word)
+                            .first()
+                            .map(|entry| entry.word.clone())
+                            .or_else(|| Word::from_xsampa(&word))
+                    },
+                };
+                match rhymed.and_then(|rhymed| Rhyme::new(&rhymed, rhyme_kind)) {
+                    Some(rhyme) => rhyme,
+                    None => {
+                        eprintln!("{} is neither in the dictionary nor a word in X-SAMPA", word);
+                        process::exit(1);
+                    }
+                }
+            });
+            if nearest > 0 || metrics {
+                check_loaded(generator.load_real_words());
+            }
+            let derive = !affixes.is_empty() || part_of_speech.is_some();
+            let g2p = (derive || inflect).then(|| {
+                check_loaded(generator.load_syllablized_phonemes());
+                let mut logger = TerminalLogger::new();
+                logger.initialize();
+                let g2p = G2p::new(&generator.syllablized_phonemes, &mut logger);
+                logger.finish();
+                g2p
+            });
+            let inventory = g2p.as_ref().filter(|_| derive).map(|g2p| AffixInventory::learn(&g2p.lexicon));
+            let chosen: Vec<&Affix> = match &inventory {
+                Some(inventory) => affixes.iter().map(|name| inventory.find(name).unwrap_or_else(|| {
+                    eprintln!("{} is not an affix found in the dictionary", name);
+                    process::exit(1);
+                })).collect(),
+                None => vec![],
+            };
+            if let (Some(inventory), Some(part_of_speech)) = (&inventory, part_of_speech) {
+                if inventory.choose_suffix(part_of_speech, &mut generator.rng).is_none() {
+                    eprintln!("No suffixes that make {}s were found in the dictionary", format!("{:?}", part_of_speech).to_lowercase());
+                    process::exit(1);
+                }
+            }
+
+            for _ in 0..count {
+                let mut res = match &rhyme {
+                    Some(rhyme) => match generator.generate_rhyme(rhyme) {
+                        Some(word) => word,
+                        None => {
+                            println!("no rhyming word found");
+                            continue;
+                        }
+                    },
+                    None => match generator.generate_word() {
+                        Some(word) => word,
+                        None => {
+                            println!("no word could be generated far enough from real words that fits the batch");
+                            continue;
+                        }
+                    },
+                };
+                let mut spelling = g2p.as_ref().map(|g2p| g2p.predict_spelling(&res));
+                if let (Some(inventory), Some(spelled)) = (&inventory, &mut spelling) {
+                    let mut affixes = chosen.clone();
+                    if !affixes.iter().any(|affix| affix.position == AffixPosition::Suffix) {
+                        affixes.extend(part_of_speech.and_then(|part_of_speech| inventory.choose_suffix(part_of_speech, &mut generator.rng)));
+                    }
+                    (res, *spelled) = morphology::derive(&res, spelled, &affixes);
+                }
+                let mut line = match &spelling {
+                    Some(spelling) => format!("{}: {}", spelling, output.format(&res)),
+                    None => output.format(&res),
+                };
+                // inflected before sound changes, which the spelling doesn't follow
+                let inflected: Vec<String> = match spelling.as_ref().filter(|_| inflect) {
+                    Some(spelling) => Inflection::of(part_of_speech)
+                        .iter()
+                        .map(|inflection| format!("  {} {}: {}", inflection.name(), inflection.spell(&res, spelling), output.format(&res.inflect(*inflection))))
+                        .collect(),
+                    None => vec![],
+                };
+                if let Some(changes) = &sound_changes {
+                    let (changed, _trace) = changes.apply(&res);
+                    line += &format!(" > {}", output.format(&changed));
+                    res = changed;
+                }
+                if narrow {
+                    line += &format!(" [{}]", output.notation.write(&res.to_narrow_ipa()));
+                }
+                if let Some(real_words) = generator.real_words.as_ref().filter(|_| nearest > 0) {
+                    let closest: Vec<&str> = real_words.nearest(&res, nearest)
+                        .into_iter()
+                        .map(|neighbor| neighbor.entry.spelling.as_str())
+                        .collect();
+                    match real_words.novelty(&res) {
+                        Some(novelty) => line += &format!(" novelty {:.2} (near {})", novelty, closest.join(", ")),
+                        None => line += " novelty none",
+                    }
+                }
+                if let Some(measured) = generator.measure(&res).filter(|_| metrics) {
+                    line += &format!(" {}", measured);
+                }
+                println!("{}", line);
+                for form in inflected {
+                    println!("{}", form);
+                }
+                if speak {
+                    tts.speak(&output.dialect.to_english(&res)).unwrap();
+                }
+            }
+        },
+        Command::Stats { top, max_syllables } => {
+            // a phonology has no training words to summarize
+            if cli.phonology.is_none() {
+                check_loaded(generator.load_syllablized_phonemes());
+            }
+            let stats_config = StatsConfig { top, max_syllables, ..Default::default() };
+            let stats = ModelStats::compute(&generator.syllablized_phonemes, &generator.sonority_graph, &generator.syllable_connections, &stats_config);
+            println!("{}", stats);
+        },
+        Command::SoundChange { file, words } => {
+            let sound_changes = load_sound_changes(&file);
+            check_loaded(generator.load_syllablized_phonemes());
+
+            for word in words {
+                let Some((_, syllables)) = generator.syllablized_phonemes.words.iter().find(|(known, _)| *known == word) else {
+                    println!("{}: not in the dictionary\n", word);
+                    continue;
+                };
+                let original = Word::new(syllables.clone());
+                let (changed, trace) = sound_changes.apply(&original);
+                println!("{}: {} > {}\n{}", word, original, changed, trace);
+            }
+        },
+        Command::Pronounce { words, metrics, output } => {
+            check_loaded(generator.load_syllablized_phonemes());
+            if metrics {
+                check_loaded(generator.load_real_words());
+            }
+            let mut logger = TerminalLogger::new();
+            logger.initialize();
+            let g2p = G2p::new(&generator.syllablized_phonemes, &mut logger);
+            logger.finish();
+
+            for spelling in words {
+                let Some(word) = g2p.pronounce(&spelling) else {
+                    println!("{}: cannot be pronounced", spelling);
+                    continue;
+                };
+                let source = match g2p.lookup(&spelling) {
+                    Some(_) => "dictionary",
+                    None => "predicted",
+                };
+                let score = match generator.score(&word) {
+                    Some(score) => format!("{:.2}", score),
+                    None => String::from("none"),
+                };
+                let mut line = format!("{}: {} [{}, score {}, spelled {}]", spelling, output.format(&word), source, score, g2p.predict_spelling(&word));
+                if let Some(measured) = generator.measure(&word) {
+                    line += &format!(" {}", measured);
+                }
+                println!("{}", line);
+            }
+        },
+        Command::Match { words, config, metrics, output } => {
+            check_loaded(generator.load_real_words());
+
+            for spelling in words {
+                let Some(target) = generator.real_words.as_ref().and_then(|real_words| real_words.lexicon.lookup(&spelling).first().map(|entry| entry.word.clone())) else {
+                    println!("{}: not in the dictionary", spelling);
+                    continue;
+                };
+                let describe = |generator: &FakeWordGenerator, word: &Word| match (metrics, &generator.positional_probabilities) {
+                    (true, _) => format!("{} {}", output.format(word), generator.measure(word).unwrap()),
+                    (false, Some(probabilities)) => format!("{} biphone {:.4}", output.format(word), probabilities.biphone_probability(word)),
+                    (false, None) => output.format(word),
+                };
+                println!("{}: {}", spelling, describe(&generator, &target));
+
+                let matches = check_loaded(pseudowords::matched_pseudowords(&mut generator, &target, &config));
+                for matched in matches.iter() {
+                    println!("  {}", describe(&generator, matched));
+                }
+                if matches.len() < config.matches {
+                    println!("  only {} of {} matches found", matches.len(), config.matches);
+                }
+            }
+        },
+        Command::Lookup { words, prefix, suffix, limit, output } => {
+            check_loaded(generator.load_syllablized_phonemes());
+            let lexicon = Lexicon::new(&generator.syllablized_phonemes);
+
+            for spelling in words {
+                let entries = lexicon.lookup(&spelling);
+                if entries.is_empty() {
+                    println!("{}: not in the dictionary", spelling);
+                }
+                for entry in entries {
+                    let variant = match entry.variant {
+                        0 => String::new(),
+                        n => format!(" ({})", n + 1),
+                    };
+                    let homophones: Vec<&str> = lexicon.homophones(&entry.word)
+                        .into_iter()
+                        .map(|homophone| homophone.spelling.as_str())
+                        .filter(|homophone| *homophone != entry.spelling)
+                        .collect();
+                    match homophones.is_empty() {
+                        true => println!("{}{}: {}", entry.spelling, variant, output.format(&entry.word)),
+                        false => println!("{}{}: {} [homophones: {}]", entry.spelling, variant, output.format(&entry.word), homophones.join(", ")),
+                    }
+                }
+            }
+
+            if !prefix.is_empty() || !suffix.is_empty() {
+                let matches = match (prefix.is_empty(), suffix.is_empty()) {
+                    (false, true) => lexicon.with_prefix(&prefix),
+                    (true, false) => lexicon.with_suffix(&suffix),
+                    _ => lexicon.with_prefix(&prefix)
+                        .into_iter()
+                        .filter(|entry| entry.word.phonemes().ends_with(&suffix))
+                        .collect(),
+                };
+                for entry in matches.iter().take(limit) {
+                    println!("{}: {}", entry.spelling, output.format(&entry.word));
+                }
+                println!("{} words in total", matches.len());
+            }
+        },
+        Command::Blend { words, top, output } => {
+            check_loaded(generator.load_syllablized_phonemes());
+            let mut logger = TerminalLogger::new();
+            logger.initialize();
+            let g2p = G2p::new(&generator.syllablized_phonemes, &mut logger);
+            logger.finish();
+
+            let [first, second] = [&words[0], &words[1]].map(|word| {
+                match g2p.lookup(word).cloned().or_else(|| Word::from_ipa(word)) {
+                    Some(parsed) => parsed,
+                    None => {
+                        eprintln!("{} is neither in the dictionary nor a word in IPA", word);
+                        process::exit(1);
+                    }
+                }
+            });
+            println!("{}: {}\n{}: {}", words[0], output.format(&first), words[1], output.format(&second));
+
+            let blends = blend::blends(&first, &second, &generator, &g2p);
+            if blends.is_empty() {
+                println!("no blends found");
+            }
+            for blended in blends.iter().take(top) {
+                println!("{}: {} [score {:.2}, overlap {}]", blended.spelling, output.format(&blended.word), blended.score, blended.overlap);
+            }
+        },
+        Command::Transcribe { .. } => unreachable!("transcribing is handled before the model is loaded"),
     }
 }
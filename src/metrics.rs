@@ -0,0 +1,117 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{lexicon::Lexicon, neighbors::{self, BkTree}, phoneme::Phoneme, word::Word};
+
+/// How often segments and pairs of segments appear at each position of the words of a lexicon,
+/// as described by Vitevitch & Luce (2004).
+///
+/// The chance of a segment at a position is the log frequency of the words with that segment
+/// there, out of the log frequency of every word long enough to have that position. Every word
+/// counts at least 1, so words missing from the frequency list still count.
+pub struct PositionalProbabilities {
+    segments: HashMap<(usize, Phoneme), f64>,
+    segment_totals: Vec<f64>,
+    biphones: HashMap<(usize, Phoneme, Phoneme), f64>,
+    biphone_totals: Vec<f64>,
+}
+
+impl PositionalProbabilities {
+    /// Counts the main pronunciation of every word in `lexicon`.
+    pub fn new(lexicon: &Lexicon) -> Self {
+        let mut probabilities = Self {
+            segments: HashMap::new(),
+            segment_totals: Vec::new(),
+            biphones: HashMap::new(),
+            biphone_totals: Vec::new(),
+        };
+
+        for entry in lexicon.entries().iter().filter(|entry| entry.variant == 0) {
+            let weight = 1.0 + entry.log_frequency();
+            let phonemes = entry.word.phonemes();
+            for (i, phoneme) in phonemes.iter().enumerate() {
+                *probabilities.segments.entry((i, *phoneme)).or_default() += weight;
+                add_total(&mut probabilities.segment_totals, i, weight);
+            }
+            for (i, pair) in phonemes.windows(2).enumerate() {
+                *probabilities.biphones.entry((i, pair[0], pair[1])).or_default() += weight;
+                add_total(&mut probabilities.biphone_totals, i, weight);
+            }
+        }
+
+        probabilities
+    }
+
+    /// Returns the chance of each phoneme of `word` at its position.
+    pub fn segment_probabilities(&self, word: &Word) -> Vec<f64> {
+        word.phonemes()
+            .iter()
+            .enumerate()
+            .map(|(i, phoneme)| ratio(self.segments.get(&(i, *phoneme)), self.segment_totals.get(i)))
+            .collect()
+    }
+
+    /// Returns the chance of each pair of adjacent phonemes of `word` at its position.
+    pub fn biphone_probabilities(&self, word: &Word) -> Vec<f64> {
+        word.phonemes()
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| ratio(self.biphones.get(&(i, pair[0], pair[1])), self.biphone_totals.get(i)))
+            .collect()
+    }
+
+    /// The sum of the positional segment probabilities of every phoneme of `word`.
+    pub fn segment_probability(&self, word: &Word) -> f64 {
+        self.segment_probabilities(word).iter().sum()
+    }
+
+    /// The sum of the positional biphone probabilities of every pair of adjacent phonemes of `word`.
+    pub fn biphone_probability(&self, word: &Word) -> f64 {
+        self.biphone_probabilities(word).iter().sum()
+    }
+}
+
+fn add_total(totals: &mut Vec<f64>, i: usize, weight: f64) {
+    if totals.len() <= i {
+        totals.resize(i + 1, 0.0);
+    }
+    totals[i] += weight;
+}
+
+fn ratio(count: Option<&f64>, total: Option<&f64>) -> f64 {
+    match (count, total) {
+        (Some(count), Some(total)) if *total > 0.0 => count / total,
+        _ => 0.0,
+    }
+}
+
+
+/// The measures psycholinguists match nonword stimuli on.
+#[derive(Clone, Copy, Debug)]
+pub struct WordMetrics {
+    /// How many real words are one phoneme away.
+    pub density: usize,
+    /// The summed log frequencies of the real words one phoneme away.
+    pub weighted_density: f64,
+    /// The sum of the positional segment probabilities of every phoneme.
+    pub segment_probability: f64,
+    /// The sum of the positional biphone probabilities of every pair of adjacent phonemes.
+    pub biphone_probability: f64,
+}
+
+impl WordMetrics {
+    pub fn measure(word: &Word, real_words: &BkTree, probabilities: &PositionalProbabilities) -> Self {
+        let neighbors = real_words.neighbors(word);
+        Self {
+            density: neighbors.len(),
+            weighted_density: neighbors::weighted_count(&neighbors),
+            segment_probability: probabilities.segment_probability(word),
+            biphone_probability: probabilities.biphone_probability(word),
+        }
+    }
+}
+
+impl fmt::Display for WordMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "density {} (weighted {:.2}), segment {:.4}, biphone {:.4}", self.density, self.weighted_density, self.segment_probability, self.biphone_probability)
+    }
+}
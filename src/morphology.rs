@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{lexicon::Lexicon, phoneme::Phoneme, word::Word};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PartOfSpeech {
+    Noun,
+    Verb,
+    Adjective,
+    Adverb,
+}
+
+impl PartOfSpeech {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "noun" => Some(Self::Noun),
+            "verb" => Some(Self::Verb),
+            "adjective" | "adj" => Some(Self::Adjective),
+            "adverb" | "adv" => Some(Self::Adverb),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AffixPosition {
+    Prefix,
+    Suffix,
+}
+
+/// The English affixes looked for in the dictionary. A suffix is listed with the part of speech
+/// of the words it makes, a prefix with the part of speech of the words it attaches to.
+const ENGLISH_AFFIXES: &[(&str, AffixPosition, PartOfSpeech)] = &[
+    ("ation", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ion", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ness", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ment", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ity", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("er", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ist", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ism", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ship", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("hood", AffixPosition::Suffix, PartOfSpeech::Noun),
+    ("ize", AffixPosition::Suffix, PartOfSpeech::Verb),
+    ("ify", AffixPosition::Suffix, PartOfSpeech::Verb),
+    ("ate", AffixPosition::Suffix, PartOfSpeech::Verb),
+    ("en", AffixPosition::Suffix, PartOfSpeech::Verb),
+    ("ous", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("ful", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("less", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("able", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("ive", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("al", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("ic", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("ish", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("y", AffixPosition::Suffix, PartOfSpeech::Adjective),
+    ("ly", AffixPosition::Suffix, PartOfSpeech::Adverb),
+    ("un", AffixPosition::Prefix, PartOfSpeech::Adjective),
+    ("in", AffixPosition::Prefix, PartOfSpeech::Adjective),
+    ("non", AffixPosition::Prefix, PartOfSpeech::Adjective),
+    ("re", AffixPosition::Prefix, PartOfSpeech::Verb),
+    ("dis", AffixPosition::Prefix, PartOfSpeech::Verb),
+    ("mis", AffixPosition::Prefix, PartOfSpeech::Verb),
+    ("pre", AffixPosition::Prefix, PartOfSpeech::Verb),
+    ("over", AffixPosition::Prefix, PartOfSpeech::Verb),
+    ("under", AffixPosition::Prefix, PartOfSpeech::Verb),
+    ("out", AffixPosition::Prefix, PartOfSpeech::Verb),
+    ("sub", AffixPosition::Prefix, PartOfSpeech::Noun),
+    ("super", AffixPosition::Prefix, PartOfSpeech::Noun),
+];
+
+/// Affixes attached to fewer dictionary words than this aren't learned.
+const MIN_DERIVATIONS: usize = 3;
+
+/// Changes at the boundary seen fewer times than this are left out.
+const MIN_CHANGES: usize = 3;
+
+/// An English prefix or suffix, pronounced the way the dictionary does.
+#[derive(Clone, Debug)]
+pub struct Affix {
+    pub spelling: String,
+    pub position: AffixPosition,
+    /// The part of speech of the words a suffix makes, or of the words a prefix attaches to.
+    pub part_of_speech: PartOfSpeech,
+    pub phonemes: Vec<Phoneme>,
+    /// How many dictionary words are another dictionary word with this affix attached.
+    pub derivations: usize,
+    /// Changes to the last phoneme of a stem before a suffix, like the /t/ of create becoming the
+    /// /ʃ/ of creation.
+    pub changes: HashMap<Phoneme, Phoneme>,
+    /// The syllable stressed by affixes that move the stress, counted from the end of the word:
+    /// -ity always stresses the third from last. None for affixes that keep the stress of the stem.
+    pub stress: Option<usize>,
+}
+
+/// What a derived word and its stem have in common, oriented so the affix is at the end.
+struct Derivation {
+    /// The phonemes of the affix, after any changed phoneme of the stem.
+    affix: Vec<Phoneme>,
+    /// The phoneme of the stem next to the affix.
+    edge: Phoneme,
+    /// What `edge` became, if it changed.
+    change: Option<Phoneme>,
+    keeps_stress: bool,
+    /// The syllable of the derived word with primary stress, counted from the end.
+    stress_from_end: usize,
+}
+
+fn derivation(derived: &Word, stem: &Word, position: AffixPosition) -> Option<Derivation> {
+    let mut derived_phonemes = derived.phonemes();
+    let mut stem_phonemes = stem.phonemes();
+    let mut derived_stress = derived.stressed_phoneme();
+    let mut stem_stress = stem.stressed_phoneme();
+    if position == AffixPosition::Prefix {
+        derived_phonemes.reverse();
+        stem_phonemes.reverse();
+        derived_stress = derived_phonemes.len() - derived_stress;
+        stem_stress = stem_phonemes.len() - stem_stress;
+    }
+
+    // the stem has to be all there, except maybe for the phoneme next to the affix
+    let common = derived_phonemes.iter().zip(stem_phonemes.iter()).take_while(|(a, b)| a == b).count();
+    if common + 1 < stem_phonemes.len() || derived_phonemes.len() <= common { return None };
+    let edge = *stem_phonemes.last()?;
+    let mut affix = derived_phonemes[common..].to_vec();
+    // only suffixes are known to change the stem, like creation
+    let change = match common < stem_phonemes.len() {
+        true if affix.len() > 1 && position == AffixPosition::Suffix => Some(affix.remove(0)),
+        true => return None,
+        false => None,
+    };
+    if position == AffixPosition::Prefix {
+        affix.reverse();
+    }
+
+    Some(Derivation {
+        affix,
+        edge,
+        change,
+        keeps_stress: derived_stress == stem_stress,
+        stress_from_end: derived.syllables.len() - derived.stressed_syllable(),
+    })
+}
+
+/// Returns the most common key, breaking ties by the ARPAbet of the phonemes so the same
+/// dictionary always gives the same affixes.
+fn most_common<K: Clone>(counts: &HashMap<K, usize>, arpabet: impl Fn(&K) -> String) -> Option<(K, usize)> {
+    counts
+        .iter()
+        .max_by(|(a, x), (b, y)| x.cmp(y).then_with(|| arpabet(b).cmp(&arpabet(a))))
+        .map(|(key, count)| (key.clone(), *count))
+}
+
+fn arpabet(phonemes: &[Phoneme]) -> String {
+    phonemes.iter().map(|phoneme| phoneme.to_arpabet()).collect::<Vec<_>>().join(" ")
+}
+
+impl Affix {
+    /// The phonemes [`Affix::attach`] can add that aren't learned from the dictionary, which the
+    /// inventory in use has to define.
+    pub const PHONEMES: [Phoneme; 1] = [Phoneme::M];
+
+    /// Learns how an affix is pronounced from the words of `lexicon` that are another word with
+    /// the affix attached, like darkness and dark or rewrite and write.
+    ///
+    /// Returns none if too few words have it.
+    pub fn learn(lexicon: &Lexicon, spelling: &str, position: AffixPosition, part_of_speech: PartOfSpeech) -> Option<Self> {
+        let mut derivations = Vec::new();
+        for entry in lexicon.entries().iter().filter(|entry| entry.variant == 0 && entry.spelling.len() > spelling.len() + 2) {
+            let stem = match position {
+                AffixPosition::Prefix => entry.spelling.strip_prefix(spelling),
+                AffixPosition::Suffix => entry.spelling.strip_suffix(spelling),
+            };
+            let Some(stem) = stem else { continue };
+            // suffixes starting with a vowel take the silent e off their stem, like creation
+            let stems = match position {
+                AffixPosition::Prefix => vec![stem.to_owned()],
+                AffixPosition::Suffix => vec![stem.to_owned(), format!("{}e", stem)],
+            };
+            let found = stems.iter().find_map(|stem| lexicon.lookup(stem).into_iter().find(|stem| stem.variant == 0));
+            let Some(stem) = found.filter(|stem| stem.spelling.len() > 2) else { continue };
+            derivations.extend(derivation(&entry.word, &stem.word, position));
+        }
+        if derivations.len() < MIN_DERIVATIONS { return None };
+
+        let mut pronunciations: HashMap<Vec<Phoneme>, usize> = HashMap::new();
+        let mut edges: HashMap<Phoneme, usize> = HashMap::new();
+        let mut changes: HashMap<(Phoneme, Phoneme), usize> = HashMap::new();
+        let mut stresses: HashMap<usize, usize> = HashMap::new();
+        for derivation in derivations.iter() {
+            *pronunciations.entry(derivation.affix.clone()).or_default() += 1;
+            *edges.entry(derivation.edge).or_default() += 1;
+            if let Some(change) = derivation.change {
+                *changes.entry((derivation.edge, change)).or_default() += 1;
+            }
+            *stresses.entry(derivation.stress_from_end).or_default() += 1;
+        }
+        let (phonemes, _) = most_common(&pronunciations, |phonemes| arpabet(phonemes))?;
+
+        // a change is learned if most stems ending in its phoneme go through it
+        let learned_changes: HashMap<Phoneme, Phoneme> = changes
+            .into_iter()
+            .filter(|((from, _), count)| *count >= MIN_CHANGES && count * 2 > edges[from])
+            .map(|((from, to), _)| (from, to))
+            .collect();
+
+        // an affix moves the stress if its words rarely keep their stem's and nearly always
+        // stress the same syllable
+        let kept = derivations.iter().filter(|derivation| derivation.keeps_stress).count();
+        let (stress, stressed) = most_common(&stresses, |position| position.to_string())?;
+        let stress = (kept * 2 < derivations.len() && stressed * 10 >= derivations.len() * 9).then_some(stress);
+
+        Some(Self {
+            spelling: spelling.to_owned(),
+            position,
+            part_of_speech,
+            phonemes,
+            derivations: derivations.len(),
+            changes: learned_changes,
+            stress,
+        })
+    }
+
+    /// The affix with a hyphen where the stem goes, like un- or -ous.
+    pub fn label(&self) -> String {
+        match self.position {
+            AffixPosition::Prefix => format!("{}-", self.spelling),
+            AffixPosition::Suffix => format!("-{}", self.spelling),
+        }
+    }
+
+    /// Attaches the affix to `stem`, spelled `spelling`, returning the new word and its spelling.
+    ///
+    /// The boundary is adjusted the way English does it: the stem's last phoneme changes the
+    /// way it did in the dictionary (create, creation), a final schwa is dropped before a vowel
+    /// (drama, dramatize), the same phoneme on both sides is said once, in- becomes im-, il- and
+    /// ir- before labials, /l/ and /r/, and the spelling loses a silent e or changes y to i. The
+    /// word is syllabified again and stressed like its stem unless the affix moves the stress.
+    pub fn attach(&self, stem: &Word, spelling: &str) -> (Word, String) {
+        let mut phonemes = stem.phonemes();
+        let mut affix = self.phonemes.clone();
+        let mut stem_spelling = spelling.to_owned();
+        let mut affix_spelling = self.spelling.clone();
+        let mut stressed = stem.stressed_phoneme();
+
+        let starts_with_vowel = |spelling: &str| spelling.starts_with(['a', 'e', 'i', 'o', 'u', 'y']);
+        match self.position {
+            AffixPosition::Suffix => {
+                if let Some(last) = phonemes.last_mut() {
+                    *last = self.changes.get(last).copied().unwrap_or(*last);
+                }
+                let silent_e = phonemes.last().is_some_and(|phone| !phone.is_syllabic());
+                let dropped_schwa = phonemes.len() > 1 && phonemes.last() == Some(&Phoneme::AH) && affix.first().is_some_and(|phone| phone.is_syllabic());
+                if dropped_schwa || (phonemes.len() > 1 && phonemes.last() == affix.first() && !affix[0].is_syllabic()) {
+                    phonemes.pop();
+                }
+
+                if starts_with_vowel(&affix_spelling) && ((silent_e && stem_spelling.ends_with('e')) || (dropped_schwa && stem_spelling.ends_with(['a', 'e', 'o']))) {
+                    stem_spelling.pop();
+                }
+                let before_y = stem_spelling.strip_suffix('y').and_then(|rest| rest.chars().last());
+                if before_y.is_some_and(|letter| !"aeiou".contains(letter)) && !affix_spelling.starts_with('i') {
+                    stem_spelling.pop();
+                    stem_spelling.push('i');
+                }
+            },
+            AffixPosition::Prefix => {
+                if self.spelling == "in" && affix.last() == Some(&Phoneme::N) {
+                    match phonemes.first().copied() {
+                        Some(Phoneme::P | Phoneme::B | Phoneme::M) => {
+                            affix.pop();
+                            affix.push(Phoneme::M);
+                            affix_spelling = String::from("im");
+                        },
+                        Some(Phoneme::L) => {
+                            affix.pop();
+                            affix_spelling = String::from("il");
+                        },
+                        Some(Phoneme::R) => {
+                            affix.pop();
+                            affix_spelling = String::from("ir");
+                        },
+                        _ => {},
+                    }
+                }
+                if affix.last() == phonemes.first() && !phonemes[0].is_syllabic() {
+                    affix.pop();
+                }
+                stressed += affix.len();
+            },
+        }
+
+        let (boundary, phonemes, spelling) = match self.position {
+            AffixPosition::Prefix => (affix.len(), [affix, phonemes].concat(), affix_spelling + &stem_spelling),
+            AffixPosition::Suffix => (phonemes.len(), [phonemes, affix].concat(), stem_spelling + &affix_spelling),
+        };
+        let mut word = Word::resyllabify(&phonemes, boundary, stressed);
+        if let Some(from_end) = self.stress {
+            word.stress_syllable(word.syllables.len().saturating_sub(from_end));
+        }
+        (word, spelling)
+    }
+}
+
+/// The English affixes found in the dictionary.
+pub struct AffixInventory {
+    pub affixes: Vec<Affix>,
+}
+
+impl AffixInventory {
+    /// Learns every English affix that enough words of `lexicon` have.
+    pub fn learn(lexicon: &Lexicon) -> Self {
+        Self {
+            affixes: ENGLISH_AFFIXES
+                .iter()
+                .filter_map(|(spelling, position, part_of_speech)| Affix::learn(lexicon, spelling, *position, *part_of_speech))
+                .collect(),
+        }
+    }
+
+    /// Finds an affix by its label, like un- or -ous, or its spelling alone.
+    pub fn find(&self, name: &str) -> Option<&Affix> {
+        let name = name.to_lowercase();
+        self.affixes
+            .iter()
+            .find(|affix| affix.label() == name)
+            .or_else(|| self.affixes.iter().find(|affix| affix.spelling == name))
+    }
+
+    /// Picks a suffix that makes words of `part_of_speech`, suffixes that more words have being
+    /// more likely.
+    pub fn choose_suffix<R: Rng>(&self, part_of_speech: PartOfSpeech, rng: &mut R) -> Option<&Affix> {
+        let suffixes: Vec<&Affix> = self.affixes
+            .iter()
+            .filter(|affix| affix.position == AffixPosition::Suffix && affix.part_of_speech == part_of_speech)
+            .collect();
+        suffixes.choose_weighted(rng, |affix| affix.derivations).ok().copied()
+    }
+}
+
+/// Attaches `affixes` to `stem`: suffixes in order after it, and prefixes in order before it,
+/// the last one closest to the stem.
+pub fn derive(stem: &Word, spelling: &str, affixes: &[&Affix]) -> (Word, String) {
+    let mut derived = (stem.clone(), spelling.to_owned());
+    for affix in affixes.iter().filter(|affix| affix.position == AffixPosition::Suffix) {
+        derived = affix.attach(&derived.0, &derived.1);
+    }
+    for affix in affixes.iter().rev().filter(|affix| affix.position == AffixPosition::Prefix) {
+        derived = affix.attach(&derived.0, &derived.1);
+    }
+    derived
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{syllable::Syllable, syllablize::SyllablizedPhonemes};
+
+    use super::*;
+
+    fn word(arpabet: &str) -> Word {
+        Word::new(arpabet.split('.').map(|syllable| Syllable::from_arpabet(syllable).unwrap()).collect())
+    }
+
+    fn phonemes(arpabet: &str) -> Vec<Phoneme> {
+        arpabet.split_whitespace().map(|symbol| Phoneme::try_from_arpabet(symbol).unwrap()).collect()
+    }
+
+    fn lexicon(words: &[(&str, &str)]) -> Lexicon {
+        Lexicon::new(&SyllablizedPhonemes {
+            words: words.iter().map(|(spelling, arpabet)| (spelling.to_string(), word(arpabet).syllables)).collect(),
+            alternates: vec![],
+            frequencies: HashMap::new(),
+        })
+    }
+
+    fn affix(spelling: &str, position: AffixPosition, arpabet: &str) -> Affix {
+        Affix {
+            spelling: spelling.to_owned(),
+            position,
+            part_of_speech: PartOfSpeech::Noun,
+            phonemes: phonemes(arpabet),
+            derivations: 0,
+            changes: HashMap::new(),
+            stress: None,
+        }
+    }
+
+    /// Attaches `affix` to a word, returning its phonemes and spelling.
+    fn attach(affix: &Affix, stem: &str, spelling: &str) -> (Vec<Phoneme>, String) {
+        let (derived, spelling) = affix.attach(&word(stem), spelling);
+        (derived.phonemes(), spelling)
+    }
+
+    #[test]
+    fn learns_stem_changes() {
+        let lexicon = lexicon(&[
+            ("create", "K R IY0 . EY1 T"), ("creation", "K R IY0 . EY1 . SH AH0 N"),
+            ("relate", "R IH0 . L EY1 T"), ("relation", "R IH0 . L EY1 . SH AH0 N"),
+            ("donate", "D OW1 . N EY0 T"), ("donation", "D OW0 . N EY1 . SH AH0 N"),
+        ]);
+        let ion = Affix::learn(&lexicon, "ion", AffixPosition::Suffix, PartOfSpeech::Noun).unwrap();
+        assert_eq!(ion.phonemes, phonemes("AH N"));
+        assert_eq!(ion.changes, HashMap::from([(Phoneme::T, Phoneme::SH)]));
+        assert_eq!(ion.derivations, 3);
+        assert_eq!(ion.stress, None);
+        assert_eq!(attach(&ion, "P L OW1 . M EY2 T", "plomate"), (phonemes("P L OW M EY SH AH N"), String::from("plomation")));
+
+        assert!(Affix::learn(&lexicon, "ness", AffixPosition::Suffix, PartOfSpeech::Noun).is_none());
+    }
+
+    #[test]
+    fn learns_affixes_that_move_the_stress() {
+        let lexicon = lexicon(&[
+            ("active", "AE1 K . T IH0 V"), ("activity", "AE0 K . T IH1 . V IH0 . T IY0"),
+            ("humid", "HH Y UW1 . M IH0 D"), ("humidity", "HH Y UW0 . M IH1 . D IH0 . T IY0"),
+            ("stupid", "S T UW1 . P IH0 D"), ("stupidity", "S T UW0 . P IH1 . D IH0 . T IY0"),
+        ]);
+        let ity = Affix::learn(&lexicon, "ity", AffixPosition::Suffix, PartOfSpeech::Noun).unwrap();
+        assert_eq!(ity.phonemes, phonemes("IH T IY"));
+        assert_eq!(ity.stress, Some(3));
+
+        let (rapidity, spelling) = ity.attach(&word("R AE1 . P IH0 D"), "rapid");
+        assert_eq!(spelling, "rapidity");
+        assert_eq!(rapidity.syllables.len(), 4);
+        assert_eq!(rapidity.stressed_syllable(), 1);
+    }
+
+    #[test]
+    fn suffixes_keep_the_stress_of_the_stem() {
+        let ness = affix("ness", AffixPosition::Suffix, "N AH S");
+        let (darkness, _) = ness.attach(&word("D AA1 R K"), "dark");
+        assert_eq!(darkness.stressed_syllable(), 0);
+        let (forgetness, _) = ness.attach(&word("F ER0 . G EH1 T"), "forget");
+        assert_eq!(forgetness.stressed_syllable(), 1);
+    }
+
+    #[test]
+    fn final_schwas_are_dropped_before_vowels() {
+        let ize = affix("ize", AffixPosition::Suffix, "AY Z");
+        assert_eq!(attach(&ize, "D R AA1 . M AH0", "drama"), (phonemes("D R AA M AY Z"), String::from("dramize")));
+        let ful = affix("ful", AffixPosition::Suffix, "F AH L");
+        assert_eq!(attach(&ful, "D R AA1 . M AH0", "drama"), (phonemes("D R AA M AH F AH L"), String::from("dramaful")));
+    }
+
+    #[test]
+    fn the_same_consonant_is_said_once() {
+        let un = affix("un", AffixPosition::Prefix, "AH N");
+        assert_eq!(attach(&un, "N AE1 . CH ER0 . AH0 L", "natural"), (phonemes("AH N AE CH ER AH L"), String::from("unnatural")));
+        let ness = affix("ness", AffixPosition::Suffix, "N AH S");
+        assert_eq!(attach(&ness, "TH IH1 N", "thin"), (phonemes("TH IH N AH S"), String::from("thinness")));
+    }
+
+    #[test]
+    fn in_assimilates_to_the_stem() {
+        let prefix = affix("in", AffixPosition::Prefix, "IH N");
+        assert_eq!(attach(&prefix, "P AA1 . S AH0 . B AH0 L", "possible"), (phonemes("IH M P AA S AH B AH L"), String::from("impossible")));
+        assert_eq!(attach(&prefix, "L IY1 . G AH0 L", "legal"), (phonemes("IH L IY G AH L"), String::from("illegal")));
+        assert_eq!(attach(&prefix, "R EH1 . G Y AH0 . L ER0", "regular"), (phonemes("IH R EH G Y AH L ER"), String::from("irregular")));
+        assert_eq!(attach(&prefix, "AE1 K . T IH0 V", "active"), (phonemes("IH N AE K T IH V"), String::from("inactive")));
+
+        let (impossible, _) = prefix.attach(&word("P AA1 . S AH0 . B AH0 L"), "possible");
+        assert_eq!(impossible.stressed_syllable(), 1);
+    }
+
+    #[test]
+    fn spellings_lose_silent_e_and_change_y_to_i() {
+        let ful = affix("ful", AffixPosition::Suffix, "F AH L");
+        let ive = affix("ive", AffixPosition::Suffix, "IH V");
+        let ness = affix("ness", AffixPosition::Suffix, "N AH S");
+        assert_eq!(attach(&ful, "HH OW1 P", "hope").1, "hopeful");
+        assert_eq!(attach(&ive, "K R IY0 . EY1 T", "create").1, "creative");
+        assert_eq!(attach(&ness, "HH AE1 . P IY0", "happy").1, "happiness");
+        assert_eq!(attach(&ful, "P L EY1", "play").1, "playful");
+        assert_eq!(attach(&ful, "P IH1 . T IY0", "pity").1, "pitiful");
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{features::{Height, Manner, Place}, phoneme::Phoneme, syllable::{Stress, Syllable}, word::Word};
+
+/// Which rules of English allophony [`transcribe`] applies.
+#[derive(Clone, Copy, Debug)]
+pub struct NarrowConfig {
+    /// Voiceless stops are aspirated at the start of a stressed or word initial syllable: [pʰ].
+    pub aspiration: bool,
+    /// /t d/ become a flap between a vowel and an unstressed vowel: [ɾ].
+    pub flapping: bool,
+    /// /l/ is velarized after the nucleus and when syllabic: [ɫ].
+    pub dark_l: bool,
+    /// Lax, non-high vowels in unstressed syllables become schwa: [ə].
+    pub vowel_reduction: bool,
+    /// /n/ takes the place of articulation of a following consonant: [m], [ɱ], [n̪], [ŋ].
+    pub nasal_assimilation: bool,
+}
+
+impl Default for NarrowConfig {
+    fn default() -> Self {
+        Self {
+            aspiration: true,
+            flapping: true,
+            dark_l: true,
+            vowel_reduction: true,
+            nasal_assimilation: true,
+        }
+    }
+}
+
+
+/// Returns true if `syllable` is unlikely to be stressed because its nucleus is a schwa or a
+/// syllabic consonant.
+///
+/// The lexicon writes schwa as an unstressed AH, the same symbol as the vowel of cut, so AH is
+/// only a schwa in an unstressed syllable. Generated syllables are unstressed, so their AH is
+/// taken to be a schwa too.
+pub fn is_reduced(syllable: &Syllable) -> bool {
+    syllable.phonemes().iter().filter(|phone| phone.is_syllabic()).all(|phone| match *phone {
+        Phoneme::AX | Phoneme::AXR | Phoneme::IX => true,
+        Phoneme::AH => syllable.stress() == Stress::Unstressed,
+        phone => !phone.is_vowel(),
+    })
+}
+
+/// Returns the stress of each syllable of `word`.
+///
+/// Generated words have no stress, so they are given primary stress on the first syllable that
+/// doesn't have a reduced nucleus.
+pub fn stresses(word: &Word) -> Vec<Stress> {
+    let known: Vec<Stress> = word.syllables.iter().map(|syl| syl.stress()).collect();
+    if known.iter().any(|stress| *stress != Stress::Unstressed) {
+        return known;
+    }
+
+    let stressed = word.syllables
+        .iter()
+        .position(|syl| !is_reduced(syl))
+        .unwrap_or(0);
+    (0..word.syllables.len())
+        .map(|i| if i == stressed { Stress::Primary } else { Stress::Unstressed })
+        .collect()
+}
+
+/// Transcribes `word` narrowly, with stress marks and periods between syllables.
+pub fn transcribe(word: &Word, config: &NarrowConfig) -> String {
+    let stresses = stresses(word);
+
+    // every phoneme with the index of its syllable, and whether it is in the coda
+    let mut phones: Vec<(usize, bool, Phoneme)> = Vec::new();
+    for (i, syl) in word.syllables.iter().enumerate() {
+        let mut seen_nucleus = false;
+        for phone in syl.phonemes() {
+            seen_nucleus |= phone.is_syllabic();
+            phones.push((i, seen_nucleus && !phone.is_syllabic(), *phone));
+        }
+    }
+
+    let mut realized: Vec<String> = phones.iter().map(|(_, _, phone)| phone.to_ipa().to_owned()).collect();
+    for (i, (syl, coda, phone)) in phones.iter().enumerate() {
+        let features = phone.features();
+        let prev = i.checked_sub(1).map(|j| phones[j]);
+        let next = phones.get(i + 1).copied();
+        let stressed = stresses[*syl] != Stress::Unstressed;
+        let syllable_initial = prev.is_none_or(|prev| prev.0 != *syl);
+
+        if config.aspiration
+            && features.manner == Manner::Stop
+            && !features.voiced
+            && features.place != Some(Place::Glottal)
+            && syllable_initial
+            && (stressed || *syl == 0)
+        {
+            realized[i] += "ʰ";
+        }
+
+        if config.flapping
+            && features.manner == Manner::Stop
+            && features.place == Some(Place::Alveolar)
+            && prev.is_some_and(|prev| prev.2.is_vowel())
+            && next.is_some_and(|next| next.2.is_syllabic() && stresses[next.0] == Stress::Unstressed)
+        {
+            realized[i] = String::from("ɾ");
+        }
+
+        if config.dark_l
+            && features.manner == Manner::Lateral
+            && features.place == Some(Place::Alveolar)
+            && (*coda || features.syllabic)
+        {
+            realized[i] = realized[i].replacen('l', "ɫ", 1);
+        }
+
+        if config.vowel_reduction
+            && !stressed
+            && features.manner == Manner::Vowel
+            && !features.tense
+            && !features.diphthong
+            && features.height != Some(Height::High)
+        {
+            realized[i] = String::from(if features.rhotic { "ɚ" } else { "ə" });
+        }
+
+        if config.nasal_assimilation
+            && features.manner == Manner::Nasal
+            && features.place == Some(Place::Alveolar)
+            && !features.syllabic
+        {
+            let place = next.filter(|next| next.2.is_consonant()).and_then(|next| next.2.features().place);
+            match place {
+                Some(Place::Bilabial) => realized[i] = String::from("m"),
+                Some(Place::Labiodental) => realized[i] = String::from("ɱ"),
+                Some(Place::Dental) => realized[i] = String::from("n̪"),
+                Some(Place::Velar) => realized[i] = String::from("ŋ"),
+                _ => {},
+            }
+        }
+    }
+
+    let mut result = String::new();
+    for (i, stress) in stresses.iter().enumerate() {
+        if i > 0 {
+            result += ".";
+        }
+        if word.syllables.len() > 1 {
+            match stress {
+                Stress::Primary => result += "ˈ",
+                Stress::Secondary => result += "ˌ",
+                Stress::Unstressed => {},
+            }
+        }
+        for (j, (syl, _, _)) in phones.iter().enumerate() {
+            if *syl == i {
+                result += &realized[j];
+            }
+        }
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(arpabet: &str) -> Word {
+        Word::new(arpabet.split('.').map(|syllable| Syllable::from_arpabet(syllable).unwrap()).collect())
+    }
+
+    #[test]
+    fn unstressed_ah_is_reduced() {
+        let banana = word("B AH0 . N AE1 . N AH0");
+        assert!(is_reduced(&banana.syllables[0]));
+        assert!(!is_reduced(&banana.syllables[1]));
+        assert!(!is_reduced(&word("K AH1 T").syllables[0]));
+        assert!(is_reduced(&word("B AA1 . T EL").syllables[1]));
+    }
+
+    #[test]
+    fn generated_words_are_not_stressed_on_a_schwa() {
+        // about and banana without their stress digits, as the model generates them
+        assert_eq!(stresses(&word("AH . B AW T")), vec![Stress::Unstressed, Stress::Primary]);
+        assert_eq!(stresses(&word("B AH . N AE . N AH")), vec![Stress::Unstressed, Stress::Primary, Stress::Unstressed]);
+        assert_eq!(stresses(&word("AH0 . G EH1 N")), vec![Stress::Unstressed, Stress::Primary]);
+    }
+}
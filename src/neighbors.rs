@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+
+use crate::{lexicon::{Lexicon, LexiconEntry}, phoneme::Phoneme, word::Word};
+
+/// The cost of inserting or deleting a phoneme.
+///
+/// Substituting one phoneme for another costs 1 more than the number of features they differ in,
+/// so swapping similar sounds (/p/ for /b/) is cheap and swapping a vowel for a stop costs about
+/// as much as deleting one and inserting the other.
+pub const INDEL_COST: usize = 6;
+
+pub fn substitution_cost(a: Phoneme, b: Phoneme) -> usize {
+    match a == b {
+        true => 0,
+        false => 1 + a.features().difference(&b.features()),
+    }
+}
+
+/// The cheapest way to turn `a` into `b` by inserting, deleting and substituting phonemes.
+///
+/// Costs are always a metric, so distances can be indexed by a [`BkTree`].
+pub fn edit_distance(a: &[Phoneme], b: &[Phoneme]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).map(|j| j * INDEL_COST).collect();
+    for (i, a_phone) in a.iter().enumerate() {
+        let mut row = vec![(i + 1) * INDEL_COST; b.len() + 1];
+        for (j, b_phone) in b.iter().enumerate() {
+            row[j + 1] = (prev[j] + substitution_cost(*a_phone, *b_phone))
+                .min(prev[j + 1] + INDEL_COST)
+                .min(row[j] + INDEL_COST);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Returns whether `b` is `a` with one phoneme substituted, inserted or deleted.
+pub fn one_edit_apart(a: &[Phoneme], b: &[Phoneme]) -> bool {
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    let start = shorter.iter().zip(longer).take_while(|(a, b)| a == b).count();
+    match longer.len() - shorter.len() {
+        0 => start < shorter.len() && shorter[start + 1..] == longer[start + 1..],
+        1 => shorter[start..] == longer[start + 1..],
+        _ => false,
+    }
+}
+
+/// The sum of the log frequencies of `neighbors`.
+pub fn weighted_count(neighbors: &[Neighbor]) -> f64 {
+    // summing no floats gives -0
+    neighbors.iter().fold(0.0, |total, neighbor| total + neighbor.entry.log_frequency())
+}
+
+/// A real word and how far it is from the word that was searched for.
+pub struct Neighbor<'a> {
+    pub entry: &'a LexiconEntry,
+    pub distance: usize,
+}
+
+struct BkNode {
+    phonemes: Vec<Phoneme>,
+    /// Every entry of the lexicon pronounced this way.
+    entries: Vec<usize>,
+    /// `(distance, node)` for every child.
+    children: Vec<(usize, usize)>,
+}
+
+/// The pronunciations of the lexicon, indexed by [`edit_distance`] to find the real words
+/// closest to a made up one.
+///
+/// Every node's children are keyed by their distance from it, so by the triangle inequality
+/// a search only visits children whose key is within its radius of the distance to the node.
+pub struct BkTree {
+    pub lexicon: Lexicon,
+    nodes: Vec<BkNode>,
+}
+
+impl BkTree {
+    pub fn new(lexicon: Lexicon) -> Self {
+        let mut tree = Self { lexicon, nodes: Vec::new() };
+        for i in 0..tree.lexicon.len() {
+            tree.insert(i);
+        }
+        tree
+    }
+
+    fn insert(&mut self, entry: usize) {
+        let phonemes = self.lexicon.entries()[entry].word.phonemes();
+        if self.nodes.is_empty() {
+            self.nodes.push(BkNode { phonemes, entries: vec![entry], children: vec![] });
+            return;
+        }
+
+        let mut node = 0;
+        loop {
+            let distance = edit_distance(&self.nodes[node].phonemes, &phonemes);
+            if distance == 0 {
+                self.nodes[node].entries.push(entry);
+                return;
+            }
+            match self.nodes[node].children.iter().find(|(key, _)| *key == distance) {
+                Some((_, child)) => node = *child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(BkNode { phonemes, entries: vec![entry], children: vec![] });
+                    self.nodes[node].children.push((distance, child));
+                    return;
+                },
+            }
+        }
+    }
+
+    /// Visits every node within `radius` of `phonemes`. `visit` returns the radius to keep
+    /// searching with, so searches for the nearest words can narrow it as they go.
+    fn search(&self, phonemes: &[Phoneme], mut radius: usize, mut visit: impl FnMut(&BkNode, usize) -> usize) {
+        if self.nodes.is_empty() { return };
+
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let distance = edit_distance(&node.phonemes, phonemes);
+            if distance <= radius {
+                radius = visit(node, distance);
+            }
+            stack.extend(node.children
+                .iter()
+                .filter(|(key, _)| key.abs_diff(distance) <= radius)
+                .map(|(_, child)| *child));
+        }
+    }
+
+    /// Returns every real word within `radius` of `word`, closest first.
+    pub fn within(&self, word: &Word, radius: usize) -> Vec<Neighbor<'_>> {
+        let mut neighbors = Vec::new();
+        self.search(&word.phonemes(), radius, |node, distance| {
+            neighbors.extend(node.entries.iter().map(|i| Neighbor { entry: &self.lexicon.entries()[*i], distance }));
+            radius
+        });
+        neighbors.sort_by_key(|neighbor| (neighbor.distance, neighbor.entry.rank, neighbor.entry.variant));
+        neighbors
+    }
+
+    /// Returns the `count` real words closest to `word`, closest first. Ties go to the more
+    /// common words.
+    pub fn nearest(&self, word: &Word, count: usize) -> Vec<Neighbor<'_>> {
+        if count == 0 { return vec![] };
+
+        let mut nearest: Vec<(usize, usize)> = Vec::new();
+        self.search(&word.phonemes(), usize::MAX, |node, distance| {
+            nearest.extend(node.entries.iter().map(|i| (distance, *i)));
+            nearest.sort_by_key(|(distance, i)| (*distance, self.lexicon.entries()[*i].rank, *i));
+            nearest.truncate(count);
+            match nearest.len() == count {
+                true => nearest[count - 1].0,
+                false => usize::MAX,
+            }
+        });
+        nearest
+            .into_iter()
+            .map(|(distance, i)| Neighbor { entry: &self.lexicon.entries()[i], distance })
+            .collect()
+    }
+
+    /// Returns the phonological neighbors of `word`: the real words one phoneme substituted,
+    /// inserted or deleted away, however different the phonemes are. Words with several
+    /// pronunciations are only counted once.
+    pub fn neighbors(&self, word: &Word) -> Vec<Neighbor<'_>> {
+        let phonemes = word.phonemes();
+        let mut seen = HashSet::new();
+        // substituting any phoneme costs less than deleting one and inserting another
+        self.within(word, 2 * INDEL_COST - 1)
+            .into_iter()
+            .filter(|neighbor| one_edit_apart(&phonemes, &neighbor.entry.word.phonemes()))
+            .filter(|neighbor| seen.insert(neighbor.entry.spelling.as_str()))
+            .collect()
+    }
+
+    /// The number of [`BkTree::neighbors`] of `word`.
+    pub fn density(&self, word: &Word) -> usize {
+        self.neighbors(word).len()
+    }
+
+    /// The sum of the log frequencies of the neighbors of `word`, so common neighbors count for
+    /// more. Words missing from the frequency list count for nothing.
+    pub fn weighted_density(&self, word: &Word) -> f64 {
+        weighted_count(&self.neighbors(word))
+    }
+
+    /// How far `word` is from the closest real word, in phonemes inserted or deleted: 0 for a
+    /// real word, and larger the less `word` sounds like any of them.
+    ///
+    /// Returns none if the lexicon has no words.
+    pub fn novelty(&self, word: &Word) -> Option<f32> {
+        let closest = self.nearest(word, 1);
+        closest.first().map(|neighbor| neighbor.distance as f32 / INDEL_COST as f32)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAT: [Phoneme; 3] = [Phoneme::K, Phoneme::AE, Phoneme::T];
+
+    #[test]
+    fn substitutions_are_one_edit() {
+        assert!(one_edit_apart(&CAT, &[Phoneme::B, Phoneme::AE, Phoneme::T]));
+        assert!(one_edit_apart(&CAT, &[Phoneme::K, Phoneme::IH, Phoneme::T]));
+        assert!(one_edit_apart(&CAT, &[Phoneme::K, Phoneme::AE, Phoneme::P]));
+        assert!(!one_edit_apart(&CAT, &[Phoneme::B, Phoneme::IH, Phoneme::T]));
+        // swapping two phonemes takes two substitutions
+        assert!(!one_edit_apart(&CAT, &[Phoneme::K, Phoneme::T, Phoneme::AE]));
+    }
+
+    #[test]
+    fn insertions_and_deletions_are_one_edit() {
+        assert!(one_edit_apart(&CAT, &[Phoneme::S, Phoneme::K, Phoneme::AE, Phoneme::T]));
+        assert!(one_edit_apart(&CAT, &[Phoneme::K, Phoneme::AE, Phoneme::S, Phoneme::T]));
+        assert!(one_edit_apart(&CAT, &[Phoneme::K, Phoneme::AE, Phoneme::T, Phoneme::S]));
+        assert!(one_edit_apart(&CAT, &[Phoneme::K, Phoneme::AE, Phoneme::T, Phoneme::T]));
+        assert!(one_edit_apart(&[Phoneme::K, Phoneme::AE, Phoneme::T, Phoneme::S], &CAT));
+        assert!(one_edit_apart(&CAT, &[Phoneme::AE, Phoneme::T]));
+        assert!(!one_edit_apart(&CAT, &[Phoneme::S, Phoneme::K, Phoneme::AE, Phoneme::T, Phoneme::S]));
+        assert!(!one_edit_apart(&CAT, &[Phoneme::S, Phoneme::K, Phoneme::AE, Phoneme::P]));
+    }
+
+    #[test]
+    fn edge_cases() {
+        assert!(!one_edit_apart(&CAT, &CAT));
+        assert!(!one_edit_apart(&[], &[]));
+        assert!(one_edit_apart(&[], &[Phoneme::AH]));
+        assert!(one_edit_apart(&[Phoneme::AH], &[]));
+        assert!(!one_edit_apart(&[], &[Phoneme::AH, Phoneme::T]));
+    }
+}
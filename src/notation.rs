@@ -0,0 +1,191 @@
+use crate::phoneme::Phoneme;
+
+/// A way of writing down pronunciations.
+///
+/// Everything is transcribed in IPA first and then converted, so the ASCII notations can also
+/// write the output of [`Dialect`](crate::dialect::Dialect) and narrow transcriptions.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Notation {
+    #[default]
+    Ipa,
+    XSampa,
+    Kirshenbaum,
+    /// Phoneme mnemonics for espeak-ng, which reads them inside `[[` and `]]`.
+    Espeak,
+}
+
+// shared by every ASCII notation
+const ASCII: &[(&str, &str)] = &[
+    ("i", "i"), ("e", "e"), ("a", "a"), ("o", "o"), ("u", "u"),
+    ("p", "p"), ("b", "b"), ("t", "t"), ("d", "d"), ("k", "k"), ("ɡ", "g"),
+    ("f", "f"), ("v", "v"), ("s", "s"), ("z", "z"), ("h", "h"), ("x", "x"),
+    ("m", "m"), ("n", "n"), ("l", "l"), ("w", "w"), ("j", "j"),
+    ("θ", "T"), ("ð", "D"), ("ʃ", "S"), ("ʒ", "Z"), ("ŋ", "N"), ("ʔ", "?"),
+    ("ɪ", "I"), ("ɛ", "E"), ("ɑ", "A"), ("ɔ", "O"), ("ʊ", "U"), ("ʌ", "V"), ("ə", "@"),
+    ("ː", ":"), ("̃", "~"),
+];
+
+const X_SAMPA: &[(&str, &str)] = &[
+    ("æ", "{"), ("ɒ", "Q"), ("ɚ", "@`"), ("ɝ", "3`"), ("ɜ", "3"), ("ɨ", "1"), ("ʉ", "}"), ("ɐ", "6"),
+    ("ɹ", "r\\"), ("r", "r"), ("ɾ", "4"), ("ʍ", "W"), ("ɫ", "5"), ("ɱ", "F"),
+    ("ˈ", "\""), ("ˌ", "%"), ("ʰ", "_h"), ("̩", "="), ("̪", "_d"),
+];
+
+const KIRSHENBAUM: &[(&str, &str)] = &[
+    ("æ", "&"), ("ɒ", "A."), ("ɚ", "@<r>"), ("ɝ", "V\"<r>"), ("ɜ", "V\""), ("ɨ", "i\""), ("ʉ", "u\""), ("ɐ", "&\""),
+    ("ɹ", "r"), ("r", "r<trl>"), ("ɾ", "*"), ("ʍ", "w<vls>"), ("ɫ", "l<vel>"), ("ɱ", "M"),
+    ("ˈ", "'"), ("ˌ", ","), ("ʰ", "<h>"), ("̩", "-"), ("̪", "["),
+];
+
+const ESPEAK: &[(&str, &str)] = &[
+    ("ɪə", "i@"), ("ɛə", "e@"), ("ʊə", "U@"), ("l̩", "@L"),
+    ("æ", "a"), ("ɒ", "0"), ("ɚ", "3"), ("ɝ", "3:"), ("ɜ", "3"), ("ɨ", "I2"), ("ʉ", "u"), ("ɐ", "a#"),
+    ("ɹ", "r"), ("r", "r"), ("ɾ", "t#"), ("ʍ", "w"), ("ɫ", "l"), ("ɱ", "m"),
+    ("ˈ", "'"), ("ˌ", ","), ("ʰ", ""), ("̩", "-"), ("̪", ""), (".", ""),
+];
+
+impl Notation {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "ipa" => Some(Self::Ipa),
+            "x-sampa" | "xsampa" => Some(Self::XSampa),
+            "kirshenbaum" => Some(Self::Kirshenbaum),
+            "espeak" => Some(Self::Espeak),
+            _ => None,
+        }
+    }
+
+    /// Returns the `(ipa, notation)` pairs to convert with, longest IPA first.
+    fn key(self) -> Vec<(&'static str, &'static str)> {
+        let own = match self {
+            Self::Ipa => return vec![],
+            Self::XSampa => X_SAMPA,
+            Self::Kirshenbaum => KIRSHENBAUM,
+            Self::Espeak => ESPEAK,
+        };
+        let mut key: Vec<(&str, &str)> = own.iter().chain(ASCII.iter()).copied().collect();
+        key.sort_by_key(|(ipa, _)| std::cmp::Reverse(ipa.chars().count()));
+        key
+    }
+
+    /// Writes an IPA transcription in this notation. Characters the notation has no symbol
+    /// for are kept as they are.
+    pub fn write(self, ipa: &str) -> String {
+        let key = self.key();
+        convert(ipa, key.iter().copied())
+    }
+
+    /// Reads a transcription in this notation back into IPA.
+    pub fn read(self, transcription: &str) -> String {
+        let mut key: Vec<(&str, &str)> = self.key().into_iter().filter(|(_, code)| !code.is_empty()).collect();
+        key.sort_by_key(|(_, code)| std::cmp::Reverse(code.len()));
+        convert(transcription, key.iter().map(|(ipa, code)| (*code, *ipa)))
+    }
+
+    /// Transcribes a word given as the IPA of each of its syllables, separating syllables the
+    /// way the notation does.
+    pub fn transcribe(self, syllables: &[String]) -> String {
+        let separator = match self {
+            Self::Ipa => " ",
+            Self::XSampa | Self::Kirshenbaum => ".",
+            Self::Espeak => "",
+        };
+        syllables.iter().map(|ipa| self.write(ipa)).collect::<Vec<String>>().join(separator)
+    }
+}
+
+fn convert<'a>(text: &str, key: impl Iterator<Item = (&'a str, &'a str)> + Clone) -> String {
+    let mut result = String::new();
+    let mut text = text;
+
+    while let Some(first) = text.chars().next() {
+        match key.clone().find(|(from, _)| text.starts_with(from)) {
+            Some((from, to)) => {
+                result += to;
+                text = &text[from.len()..];
+            },
+            None => {
+                result.push(first);
+                text = &text[first.len_utf8()..];
+            },
+        }
+    }
+
+    result
+}
+
+/// Splits an IPA transcription into phonemes of the inventory in use, ignoring stress marks,
+/// length marks and syllable breaks.
+///
+/// Returns none if part of the transcription isn't a phoneme.
+pub fn ipa_to_phonemes(ipa: &str) -> Option<Vec<Phoneme>> {
+    let mut all: Vec<(Phoneme, &str)> = Phoneme::all().into_iter().map(|phoneme| (phoneme, phoneme.to_ipa())).collect();
+    all.sort_by_key(|(_, ipa)| std::cmp::Reverse(ipa.len()));
+
+    let mut phonemes = Vec::new();
+    let mut ipa = ipa.trim();
+    while let Some(first) = ipa.chars().next() {
+        if matches!(first, 'ˈ' | 'ˌ' | 'ː' | '.' | ' ') {
+            ipa = &ipa[first.len_utf8()..];
+            continue;
+        }
+
+        match all.iter().find(|(_, check)| ipa.starts_with(check)) {
+            Some((phoneme, matched)) => {
+                phonemes.push(*phoneme);
+                ipa = &ipa[matched.len()..];
+            },
+            // English transcriptions often write /ɹ/ as a plain r
+            None if first == 'r' => {
+                phonemes.push(all.iter().find(|(_, check)| *check == "ɹ")?.0);
+                ipa = &ipa[first.len_utf8()..];
+            },
+            None => return None,
+        }
+    }
+    Some(phonemes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_notations_round_trip_every_phoneme() {
+        for notation in [Notation::XSampa, Notation::Kirshenbaum] {
+            for phoneme in Phoneme::all() {
+                let ipa = phoneme.to_ipa();
+                assert_eq!(notation.read(&notation.write(ipa)), ipa, "{:?} in {:?}", phoneme, notation);
+            }
+            let narrow = "ˈkʰæ.ɾɚ";
+            assert_eq!(notation.read(&notation.write(narrow)), narrow);
+        }
+    }
+
+    #[test]
+    fn reads_longest_symbols_first() {
+        assert_eq!(Notation::XSampa.read("r\\3`"), "ɹɝ");
+        assert_eq!(Notation::XSampa.read("3r"), "ɜr");
+        assert_eq!(Notation::Kirshenbaum.read("V\"V"), "ɜʌ");
+        assert_eq!(Notation::XSampa.write("ç"), "ç");
+    }
+
+    #[test]
+    fn parses_x_sampa_words() {
+        let phonemes = ipa_to_phonemes(&Notation::XSampa.read("\"kIt.S@n")).unwrap();
+        assert_eq!(phonemes, [Phoneme::K, Phoneme::IH, Phoneme::T, Phoneme::SH, Phoneme::AX, Phoneme::N]);
+        let phonemes = ipa_to_phonemes(&Notation::XSampa.read("tSaI")).unwrap();
+        assert_eq!(phonemes, [Phoneme::CH, Phoneme::AY]);
+    }
+
+    #[test]
+    fn ipa_edge_cases() {
+        assert_eq!(ipa_to_phonemes(""), Some(vec![]));
+        assert_eq!(ipa_to_phonemes(" ˈ. "), Some(vec![]));
+        assert_eq!(ipa_to_phonemes("ræt"), Some(vec![Phoneme::R, Phoneme::AE, Phoneme::T]));
+        assert_eq!(ipa_to_phonemes("kæç"), None);
+        assert_eq!(Notation::parse("X-SAMPA"), Some(Notation::XSampa));
+        assert_eq!(Notation::parse("sampa"), None);
+    }
+}
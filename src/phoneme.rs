@@ -1,320 +1,166 @@
-use serde::{Deserialize, Serialize};
+use core::fmt;
 
-#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
-pub enum Phoneme {
-    AA,
-    AE,
-    AH,
-    AO,
-    AW,
-    AX,
-    AXR,
-    AY,
-    EH,
-    ER,
-    EY,
-    IH,
-    IX,
-    IY,
-    OW,
-    OY,
-    UH,
-    UW,
-    UX,
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-    B,
-    CH,
-    D,
-    DH,
-    DX,
-    EL,
-    EM,
-    EN,
-    F,
-    G,
-    H,
-    JH,
-    K,
-    L,
-    M,
-    N,
-    NG,
-    NX,
-    P,
-    Q,
-    R,
-    S,
-    SH,
-    T,
-    TH,
-    V,
-    W,
-    WH,
-    Y,
-    Z,
-    ZH
-}
+use crate::{features::{Manner, PhonemeFeatures}, inventory::{Inventory, PhonemeDefinition}, notation::Notation};
+
+/// A phoneme of the inventory in use, identified by its symbol. 
+/// 
+/// Phonemes can only be created from symbols in the inventory, so every phoneme has a definition. 
+/// Symbols are ARPAbet when the default English inventory is used. 
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Phoneme(&'static str);
 
 
+/// The phonemes of the default English inventory, for rules that only apply to English. 
 impl Phoneme {
-    pub fn from_arpabet(arpabet: &str) -> Self {
-        match arpabet {
-            "AA" => Self::AA,
-            "AE" => Self::AE,
-            "AH" => Self::AH,
-            "AO" => Self::AO,
-            "AW" => Self::AW,
-            "AX" => Self::AX,
-            "AXR" => Self::AXR,
-            "AY" => Self::AY,
-            "EH" => Self::EH,
-            "ER" => Self::ER,
-            "EY" => Self::EY,
-            "IH" => Self::IH,
-            "IX" => Self::IX,
-            "IY" => Self::IY,
-            "OW" => Self::OW,
-            "OY" => Self::OY,
-            "UH" => Self::UH,
-            "UW" => Self::UW,
-            "UX" => Self::UX,
+    pub const AA: Self = Self("AA");
+    pub const AE: Self = Self("AE");
+    pub const AH: Self = Self("AH");
+    pub const AO: Self = Self("AO");
+    pub const AW: Self = Self("AW");
+    pub const AX: Self = Self("AX");
+    pub const AXR: Self = Self("AXR");
+    pub const AY: Self = Self("AY");
+    pub const EH: Self = Self("EH");
+    pub const ER: Self = Self("ER");
+    pub const EY: Self = Self("EY");
+    pub const IH: Self = Self("IH");
+    pub const IX: Self = Self("IX");
+    pub const IY: Self = Self("IY");
+    pub const OW: Self = Self("OW");
+    pub const OY: Self = Self("OY");
+    pub const UH: Self = Self("UH");
+    pub const UW: Self = Self("UW");
+    pub const UX: Self = Self("UX");
+
+    pub const B: Self = Self("B");
+    pub const CH: Self = Self("CH");
+    pub const D: Self = Self("D");
+    pub const DH: Self = Self("DH");
+    pub const DX: Self = Self("DX");
+    pub const EL: Self = Self("EL");
+    pub const EM: Self = Self("EM");
+    pub const EN: Self = Self("EN");
+    pub const F: Self = Self("F");
+    pub const G: Self = Self("G");
+    pub const H: Self = Self("H");
+    pub const JH: Self = Self("JH");
+    pub const K: Self = Self("K");
+    pub const L: Self = Self("L");
+    pub const M: Self = Self("M");
+    pub const N: Self = Self("N");
+    pub const NG: Self = Self("NG");
+    pub const NX: Self = Self("NX");
+    pub const P: Self = Self("P");
+    pub const Q: Self = Self("Q");
+    pub const R: Self = Self("R");
+    pub const S: Self = Self("S");
+    pub const SH: Self = Self("SH");
+    pub const T: Self = Self("T");
+    pub const TH: Self = Self("TH");
+    pub const V: Self = Self("V");
+    pub const W: Self = Self("W");
+    pub const WH: Self = Self("WH");
+    pub const Y: Self = Self("Y");
+    pub const Z: Self = Self("Z");
+    pub const ZH: Self = Self("ZH");
+}
 
-            "B" => Self::B,
-            "CH" => Self::CH,
-            "D" => Self::D,
-            "DH" => Self::DH,
-            "DX" => Self::DX,
-            "EL" => Self::EL,
-            "EM" => Self::EM,
-            "EN" => Self::EN,
-            "F" => Self::F,
-            "G" => Self::G,
-            "HH" => Self::H,
-            "H" => Self::H,
-            "JH" => Self::JH,
-            "K" => Self::K,
-            "L" => Self::L,
-            "M" => Self::M,
-            "N" => Self::N,
-            "NG" => Self::NG,
-            "NX" => Self::NX,
-            "P" => Self::P,
-            "Q" => Self::Q,
-            "R" => Self::R,
-            "S" => Self::S,
-            "SH" => Self::SH,
-            "T" => Self::T,
-            "TH" => Self::TH,
-            "V" => Self::V,
-            "W" => Self::W,
-            "WH" => Self::WH,
-            "Y" => Self::Y,
-            "Z" => Self::Z,
-            "ZH" => Self::ZH,
+impl Phoneme {
+    /// Returns every phoneme in the inventory in use. 
+    pub fn all() -> Vec<Phoneme> {
+        Inventory::current().phonemes.iter().map(|phoneme| Self(&phoneme.symbol)).collect()
+    }
 
-            _ => panic!("Could not convert {} to a phoneme", arpabet),
-        }
+    /// Looks up a symbol or alias in the inventory in use. 
+    pub fn try_from_arpabet(arpabet: &str) -> Option<Self> {
+        Inventory::current().get(arpabet).map(|phoneme| Self(&phoneme.symbol))
     }
 
     pub fn to_arpabet(self) -> &'static str {
-        match self {
-            Self::AA => "AA",
-            Self::AE => "AE",
-            Self::AH => "AH",
-            Self::AO => "AO",
-            Self::AW => "AW",
-            Self::AX => "AX",
-            Self::AXR => "AXR",
-            Self::AY => "AY",
-            Self::EH => "EH",
-            Self::ER => "ER",
-            Self::EY => "EY",
-            Self::IH => "IH",
-            Self::IX => "IX",
-            Self::IY => "IY",
-            Self::OW => "OW",
-            Self::OY => "OY",
-            Self::UH => "UH",
-            Self::UW => "UW",
-            Self::UX => "UX",
+        self.0
+    }
 
-            Self::B => "B",
-            Self::CH => "CH",
-            Self::D => "D",
-            Self::DH => "DH",
-            Self::DX => "DX",
-            Self::EL => "EL",
-            Self::EM => "EM",
-            Self::EN => "EN",
-            Self::F => "F",
-            Self::G => "G",
-            Self::H => "H",
-            Self::JH => "JH",
-            Self::K => "K",
-            Self::L => "L",
-            Self::M => "M",
-            Self::N => "N",
-            Self::NG => "NG",
-            Self::NX => "NX",
-            Self::P => "P",
-            Self::Q => "Q",
-            Self::R => "R",
-            Self::S => "S",
-            Self::SH => "SH",
-            Self::T => "T",
-            Self::TH => "TH",
-            Self::V => "V",
-            Self::W => "W",
-            Self::WH => "WH",
-            Self::Y => "Y",
-            Self::Z => "Z",
-            Self::ZH => "ZH",
-        }
+    pub fn try_from_ipa(ipa: &str) -> Option<Self> {
+        Inventory::current().get_by_ipa(ipa).map(|phoneme| Self(&phoneme.symbol))
     }
 
-    pub fn from_ipa(ipa: &str) -> Self {
-        match ipa {
-            "ɑ" => Self::AA, // ɑ or ɒ
-            "æ" => Self::AE,
-            "ʌ" => Self::AH,
-            "ɔ" => Self::AO,
-            "aʊ" => Self::AW,
-            "əɹ" => Self::AX, // ɚ
-            "ə" => Self::AXR,
-            "aɪ" => Self::AY,
-            "ɛ" => Self::EH,
-            "ɛɹ" => Self::ER, // ɝ
-            "eɪ" => Self::EY,
-            "ɪ" => Self::IH,
-            "ɨ" => Self::IX,
-            "i" => Self::IY,
-            "oʊ" => Self::OW,
-            "ɔɪ" => Self::OY,
-            "ʊ" => Self::UH,
-            "u" => Self::UW,
-            "ʉ" => Self::UX,
+    pub fn to_ipa(self) -> &'static str {
+        &self.definition().ipa
+    }
 
-            "b" => Self::B,
-            "tʃ" => Self::CH,
-            "d" => Self::D,
-            "ð" => Self::DH,
-            "ɾ" => Self::DX,
-            "l̩" => Self::EL,
-            "m̩" => Self::EM,
-            "n̩" => Self::EN,
-            "f" => Self::F,
-            "ɡ" => Self::G,
-            "h" => Self::H,
-            "dʒ" => Self::JH,
-            "k" => Self::K,
-            "l" => Self::L,
-            "m" => Self::M,
-            "n" => Self::N,
-            "ŋ" => Self::NG,
-            "ɾ̃" => Self::NX,
-            "p" => Self::P,
-            "ʔ" => Self::Q,
-            "ɹ" => Self::R,
-            "s" => Self::S,
-            "ʃ" => Self::SH,
-            "t" => Self::T,
-            "θ" => Self::TH,
-            "v" => Self::V,
-            "w" => Self::W,
-            "ʍ" => Self::WH,
-            "j" => Self::Y,
-            "z" => Self::Z,
-            "ʒ" => Self::ZH,
+    pub fn to_notation(self, notation: Notation) -> String {
+        notation.write(self.to_ipa())
+    }
 
-            _ => panic!("Could not convert {} to a phoneme", ipa),
-        }
+    /// Looks up a phoneme of the inventory in use by its X-SAMPA transcription. 
+    pub fn try_from_xsampa(xsampa: &str) -> Option<Self> {
+        Self::try_from_ipa(&Notation::XSampa.read(xsampa))
     }
 
-    pub fn to_ipa(self) -> &'static str {
-        match self {
-            Self::AA => "ɑ", // ɑ or ɒ
-            Self::AE => "æ",
-            Self::AH => "ʌ",
-            Self::AO => "ɔ",
-            Self::AW => "aʊ",
-            Self::AX => "əɹ", // ɚ
-            Self::AXR => "ə",
-            Self::AY => "aɪ",
-            Self::EH => "ɛ",
-            Self::ER => "ɛɹ", // ɝ
-            Self::EY => "eɪ",
-            Self::IH => "ɪ",
-            Self::IX => "ɨ",
-            Self::IY => "i",
-            Self::OW => "oʊ",
-            Self::OY => "ɔɪ",
-            Self::UH => "ʊ",
-            Self::UW => "u",
-            Self::UX => "ʉ",
+    /// Returns true if the inventory in use defines the phoneme, which is only ever false for 
+    /// the English constants. 
+    pub fn is_defined(&self) -> bool {
+        Inventory::current().get(self.0).is_some()
+    }
 
-            Self::B => "b",
-            Self::CH => "tʃ",
-            Self::D => "d",
-            Self::DH => "ð",
-            Self::DX => "ɾ",
-            Self::EL => "l̩",
-            Self::EM => "m̩",
-            Self::EN => "n̩",
-            Self::F => "f",
-            Self::G => "ɡ",
-            Self::H => "h",
-            Self::JH => "dʒ",
-            Self::K => "k",
-            Self::L => "l",
-            Self::M => "m",
-            Self::N => "n",
-            Self::NG => "ŋ",
-            Self::NX => "ɾ̃",
-            Self::P => "p",
-            Self::Q => "ʔ",
-            Self::R => "ɹ",
-            Self::S => "s",
-            Self::SH => "ʃ",
-            Self::T => "t",
-            Self::TH => "θ",
-            Self::V => "v",
-            Self::W => "w",
-            Self::WH => "ʍ",
-            Self::Y => "j",
-            Self::Z => "z",
-            Self::ZH => "ʒ",
-        }
+    /// Panics if the inventory in use doesn't define the phoneme, so rules that add English 
+    /// constants to words have to check [`Phoneme::is_defined`] first. 
+    pub fn definition(&self) -> &'static PhonemeDefinition {
+        Inventory::current()
+            .get(self.0)
+            .unwrap_or_else(|| panic!("{} is not in the {} inventory", self.0, Inventory::current().name))
     }
 
     pub fn is_vowel(&self) -> bool {
-        match self {
-            Self::AA => true,
-            Self::AE => true,
-            Self::AH => true,
-            Self::AO => true,
-            Self::AW => true,
-            Self::AX => true,
-            Self::AXR => true,
-            Self::AY => true,
-            Self::EH => true,
-            Self::ER => true,
-            Self::EY => true,
-            Self::IH => true,
-            Self::IX => true,
-            Self::IY => true,
-            Self::OW => true,
-            Self::OY => true,
-            Self::UH => true,
-            Self::UW => true,
-            Self::UX => true,
-            _ => false
-        }
+        self.features().manner == Manner::Vowel
     }
     pub fn is_consonant(&self) -> bool {
         !self.is_vowel()
     }
+    /// Returns true if the phoneme can be the nucleus of a syllable. 
+    /// In English this includes every vowel and the syllabic consonants `EL`, `EM` and `EN`. 
+    pub fn is_syllabic(&self) -> bool {
+        self.features().syllabic
+    }
+
+    pub fn features(&self) -> PhonemeFeatures {
+        self.definition().features
+    }
+
+    /// Returns the rank of the phoneme on the sonority hierarchy: 
+    /// stops and affricates (1) < fricatives (2) < nasals (3) < liquids (4) < glides (5) < vowels (6). 
+    pub fn sonority(&self) -> u8 {
+        match self.features().manner {
+            Manner::Stop | Manner::Affricate | Manner::Tap => 1,
+            Manner::Fricative => 2,
+            Manner::Nasal => 3,
+            Manner::Lateral | Manner::Rhotic => 4,
+            Manner::Glide => 5,
+            Manner::Vowel => 6,
+        }
+    }
 }
 
+impl fmt::Debug for Phoneme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for Phoneme {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Phoneme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let symbol = String::deserialize(deserializer)?;
+        Self::try_from_arpabet(&symbol).ok_or_else(|| de::Error::custom(format!("{} is not in the {} inventory", symbol, Inventory::current().name)))
+    }
+}
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
 pub enum SyllablePart {
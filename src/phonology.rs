@@ -0,0 +1,324 @@
+use core::fmt;
+use std::{collections::{HashMap, HashSet}, fs, io, ops::RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{connections::SyllableConnections, filter::InventoryError, graph::{NodeData, NodeID, SonorityGraph}, phoneme::Phoneme, syllable::Syllable};
+
+#[derive(Debug)]
+pub enum PhonologyError {
+    Io(io::Error),
+    Parse(Box<ron::error::SpannedError>),
+    UnknownPhoneme(String),
+    /// An onset or coda has a syllabic phoneme, or a nucleus has a phoneme that isn't syllabic.
+    InvalidCluster { part: &'static str, cluster: String },
+    /// A template is not made of `C`, `(C)` and exactly one `V`.
+    InvalidTemplate(String),
+    /// Every syllable is ruled out by the templates and forbidden sequences.
+    NoSyllables,
+    /// The phoneme inventory was restricted so much that no words can be generated.
+    Restriction(InventoryError),
+}
+
+impl fmt::Display for PhonologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the phonology file: {}", err),
+            Self::Parse(err) => write!(f, "could not parse the phonology file: {}", err),
+            Self::UnknownPhoneme(symbol) => write!(f, "unknown phoneme {}", symbol),
+            Self::InvalidCluster { part, cluster } => write!(f, "{:?} cannot be used as a {}", cluster, part),
+            Self::InvalidTemplate(template) => write!(f, "invalid syllable template {:?}", template),
+            Self::NoSyllables => write!(f, "the phonology does not allow any syllables"),
+            Self::Restriction(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for PhonologyError {}
+
+
+/// The onsets and codas a syllable template allows, by number of phonemes.
+///
+/// `(C)(C)V(C)` allows onsets of up to two consonants and codas of up to one.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SyllableTemplate {
+    pub onset: RangeInclusive<usize>,
+    pub coda: RangeInclusive<usize>,
+}
+
+impl SyllableTemplate {
+    pub fn parse(template: &str) -> Option<Self> {
+        let (onset, coda) = template.split_once('V')?;
+
+        fn range(margin: &str) -> Option<RangeInclusive<usize>> {
+            let optional = margin.matches("(C)").count();
+            let rest = margin.replace("(C)", "");
+            if rest.chars().any(|c| c != 'C') { return None };
+            let required = rest.len();
+            Some(required..=required + optional)
+        }
+
+        Some(Self { onset: range(onset)?, coda: range(coda)? })
+    }
+
+    pub fn allows(&self, onset: usize, coda: usize) -> bool {
+        self.onset.contains(&onset) && self.coda.contains(&coda)
+    }
+}
+
+
+/// The phonotactics of a language without a lexicon, such as a conlang.
+///
+/// Clusters are written as space separated symbols of the inventory, and an empty string is an
+/// empty onset or coda. Leaving out the onsets or codas allows only empty ones.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Phonology {
+    /// The inventory file the symbols belong to. English is used if none is given.
+    #[serde(default)]
+    pub inventory: Option<String>,
+    #[serde(default)]
+    pub onsets: Vec<(String, usize)>,
+    pub nuclei: Vec<(String, usize)>,
+    #[serde(default)]
+    pub codas: Vec<(String, usize)>,
+    /// Syllable shapes such as `CV` or `(C)V(C)`. Every shape is allowed if none are given.
+    #[serde(default)]
+    pub templates: Vec<String>,
+    /// Sequences that are never generated within a syllable. Sequences of two phonemes are also
+    /// never generated across syllables, but longer ones can still span a syllable boundary,
+    /// since syllables are only joined by their last and first phonemes.
+    #[serde(default)]
+    pub forbidden: Vec<String>,
+}
+
+impl Phonology {
+    /// Reads a phonology file. Its phonemes are not looked up until it is compiled, so its
+    /// inventory can be installed first.
+    pub fn load(path: &str) -> Result<Self, PhonologyError> {
+        let contents = fs::read_to_string(path).map_err(PhonologyError::Io)?;
+        ron::de::from_str(&contents).map_err(|err| PhonologyError::Parse(Box::new(err)))
+    }
+
+    fn parse_sequence(sequence: &str) -> Result<Vec<Phoneme>, PhonologyError> {
+        sequence
+            .split_whitespace()
+            .map(|symbol| Phoneme::try_from_arpabet(symbol).ok_or(PhonologyError::UnknownPhoneme(symbol.to_owned())))
+            .collect()
+    }
+
+    fn parse_clusters(clusters: &[(String, usize)], part: &'static str, syllabic: bool) -> Result<Vec<(Vec<Phoneme>, usize)>, PhonologyError> {
+        if clusters.is_empty() && !syllabic {
+            return Ok(vec![(vec![], 1)]);
+        }
+
+        let mut parsed = Vec::new();
+        for (cluster, weight) in clusters.iter() {
+            let phonemes = Self::parse_sequence(cluster)?;
+            let valid = match syllabic {
+                true => !phonemes.is_empty() && phonemes.iter().all(|phone| phone.is_syllabic()),
+                false => phonemes.iter().all(|phone| !phone.is_syllabic()),
+            };
+            if !valid {
+                return Err(PhonologyError::InvalidCluster { part, cluster: cluster.clone() });
+            }
+            parsed.push((phonemes, *weight));
+        }
+        Ok(parsed)
+    }
+
+    fn forbidden_sequences(&self) -> Result<Vec<Vec<Phoneme>>, PhonologyError> {
+        self.forbidden.iter().map(|sequence| Self::parse_sequence(sequence)).collect()
+    }
+
+    /// Returns every syllable the phonology allows, weighted by the product of the weights of
+    /// its onset, nucleus and coda.
+    pub fn syllables(&self) -> Result<Vec<(Syllable, usize)>, PhonologyError> {
+        let onsets = Self::parse_clusters(&self.onsets, "onset", false)?;
+        let nuclei = Self::parse_clusters(&self.nuclei, "nucleus", true)?;
+        let codas = Self::parse_clusters(&self.codas, "coda", false)?;
+        let forbidden = self.forbidden_sequences()?;
+        let templates = self.templates
+            .iter()
+            .map(|template| SyllableTemplate::parse(template).ok_or(PhonologyError::InvalidTemplate(template.clone())))
+            .collect::<Result<Vec<SyllableTemplate>, PhonologyError>>()?;
+
+        let mut syllables = Vec::new();
+        for (onset, onset_weight) in onsets.iter() {
+            for (nucleus, nucleus_weight) in nuclei.iter() {
+                for (coda, coda_weight) in codas.iter() {
+                    let weight = onset_weight * nucleus_weight * coda_weight;
+                    if weight == 0 { continue };
+                    if !templates.is_empty() && !templates.iter().any(|template| template.allows(onset.len(), coda.len())) { continue };
+
+                    let phonemes: Vec<Phoneme> = onset.iter().chain(nucleus.iter()).chain(coda.iter()).copied().collect();
+                    let has_forbidden = forbidden
+                        .iter()
+                        .any(|sequence| !sequence.is_empty() && phonemes.windows(sequence.len()).any(|window| window == sequence.as_slice()));
+                    if has_forbidden { continue };
+
+                    syllables.push((Syllable::from_phonemes(phonemes), weight));
+                }
+            }
+        }
+
+        match syllables.is_empty() {
+            true => Err(PhonologyError::NoSyllables),
+            false => Ok(syllables),
+        }
+    }
+
+    /// Builds the sonority graph and syllable connections that generate words following the
+    /// phonology.
+    ///
+    /// Any syllable can follow any other unless a forbidden pair of phonemes would meet at the
+    /// boundary; a syllable's chance of coming next is proportional to its weight. Forbidden
+    /// sequences of three or more phonemes aren't checked across the boundary.
+    pub fn compile(&self) -> Result<(SonorityGraph, SyllableConnections), PhonologyError> {
+        let syllables = self.syllables()?;
+        let forbidden = self.forbidden_sequences()?;
+
+        let mut graph = SonorityGraph { nodes: HashMap::new() };
+        let mut starts: HashMap<Phoneme, usize> = HashMap::new();
+        let mut ends: Vec<Phoneme> = Vec::new();
+        for (syllable, weight) in syllables.iter() {
+            graph.add_syllable(syllable, *weight);
+            *starts.entry(syllable.first_phoneme()).or_default() += weight;
+            if !ends.contains(&syllable.last_phoneme()) {
+                ends.push(syllable.last_phoneme());
+            }
+        }
+
+        let mut connections = SyllableConnections { connections: HashMap::new() };
+        for (start, weight) in starts.iter() {
+            connections.add_edge(NodeData::Start, NodeData::Phoneme(*start), *weight);
+        }
+        for end in ends {
+            let mut any = false;
+            for (start, weight) in starts.iter() {
+                if forbidden.iter().any(|sequence| sequence.as_slice() == [end, *start]) { continue };
+                connections.add_edge(NodeData::Phoneme(end), NodeData::Phoneme(*start), *weight);
+                any = true;
+            }
+            if !any {
+                connections.add_edge(NodeData::Phoneme(end), NodeData::Stop, 1);
+            }
+        }
+
+        Ok((graph, connections))
+    }
+}
+
+
+/// The syllables a phonology allows.
+///
+/// The compiled graph only knows which phoneme can follow which, so it can join clusters into
+/// ones the phonology rules out. Filtering generation with [`SyllableSet::allows_next`] keeps
+/// every syllable within the phonology.
+pub struct SyllableSet {
+    syllables: HashSet<Vec<Phoneme>>,
+    prefixes: HashSet<Vec<Phoneme>>,
+}
+
+impl SyllableSet {
+    pub fn new(syllables: &[(Syllable, usize)]) -> Self {
+        let mut set = Self { syllables: HashSet::new(), prefixes: HashSet::new() };
+        for (syllable, _) in syllables.iter() {
+            let phonemes = syllable.phonemes();
+            for i in 0..=phonemes.len() {
+                set.prefixes.insert(phonemes[..i].to_vec());
+            }
+            set.syllables.insert(phonemes.to_vec());
+        }
+        set
+    }
+
+    pub fn contains(&self, syllable: &Syllable) -> bool {
+        self.syllables.contains(syllable.phonemes())
+    }
+
+    /// Returns true if moving from the partial `syllable` to the node `next` can still end in
+    /// an allowed syllable.
+    ///
+    /// Meant to be used as a filter for [`SonorityGraph::evaluate_from_start_with`](crate::graph::SonorityGraph::evaluate_from_start_with).
+    pub fn allows_next(&self, syllable: &Syllable, next: NodeID) -> bool {
+        match next.data {
+            NodeData::Phoneme(phoneme) => {
+                let mut phonemes = syllable.phonemes().to_vec();
+                phonemes.push(phoneme);
+                self.prefixes.contains(&phonemes)
+            },
+            NodeData::Stop => self.contains(syllable),
+            NodeData::Start => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clusters(clusters: &[&str]) -> Vec<(String, usize)> {
+        clusters.iter().map(|cluster| (cluster.to_string(), 1)).collect()
+    }
+
+    fn phonology(onsets: &[&str], nuclei: &[&str], codas: &[&str], templates: &[&str], forbidden: &[&str]) -> Phonology {
+        Phonology {
+            inventory: None,
+            onsets: clusters(onsets),
+            nuclei: clusters(nuclei),
+            codas: clusters(codas),
+            templates: templates.iter().map(|template| template.to_string()).collect(),
+            forbidden: forbidden.iter().map(|sequence| sequence.to_string()).collect(),
+        }
+    }
+
+    fn arpabet(syllables: &[(Syllable, usize)]) -> Vec<String> {
+        let mut arpabet: Vec<String> = syllables.iter().map(|(syllable, _)| syllable.to_arpabet().trim_end().to_owned()).collect();
+        arpabet.sort();
+        arpabet
+    }
+
+    #[test]
+    fn templates_limit_onsets_and_codas() {
+        assert_eq!(SyllableTemplate::parse("(C)(C)V(C)"), Some(SyllableTemplate { onset: 0..=2, coda: 0..=1 }));
+        assert_eq!(SyllableTemplate::parse("CV"), Some(SyllableTemplate { onset: 1..=1, coda: 0..=0 }));
+        assert_eq!(SyllableTemplate::parse("CVX"), None);
+        assert_eq!(SyllableTemplate::parse("CC"), None);
+
+        let syllables = phonology(&["", "T", "S T"], &["AA"], &["", "N"], &["CV(C)"], &[]).syllables().unwrap();
+        assert_eq!(arpabet(&syllables), ["T AA", "T AA N"]);
+    }
+
+    #[test]
+    fn syllables_are_weighted_and_filtered() {
+        let mut phonology = phonology(&["", "T", "S"], &["AA", "IY"], &["", "N"], &[], &["S IY", "AA N"]);
+        phonology.onsets[1].1 = 3;
+        phonology.codas[0].1 = 0;
+        let syllables = phonology.syllables().unwrap();
+        assert_eq!(arpabet(&syllables), ["IY N", "T IY N"]);
+        assert_eq!(syllables.iter().find(|(syllable, _)| syllable.phonemes()[0] == Phoneme::T).unwrap().1, 3);
+    }
+
+    #[test]
+    fn invalid_phonologies_are_errors() {
+        assert!(matches!(phonology(&["QQ"], &["AA"], &[], &[], &[]).syllables(), Err(PhonologyError::UnknownPhoneme(symbol)) if symbol == "QQ"));
+        assert!(matches!(phonology(&["AA"], &["AA"], &[], &[], &[]).syllables(), Err(PhonologyError::InvalidCluster { part: "onset", .. })));
+        assert!(matches!(phonology(&[], &["T"], &[], &[], &[]).syllables(), Err(PhonologyError::InvalidCluster { part: "nucleus", .. })));
+        assert!(matches!(phonology(&[], &["AA"], &[], &["VV"], &[]).syllables(), Err(PhonologyError::InvalidTemplate(_))));
+        assert!(matches!(phonology(&[], &["AA"], &[], &["CV"], &[]).syllables(), Err(PhonologyError::NoSyllables)));
+    }
+
+    #[test]
+    fn forbidden_pairs_never_meet_across_syllables() {
+        let (graph, connections) = phonology(&["T", "N"], &["AA"], &["", "N"], &[], &["N N"]).compile().unwrap();
+        assert!(graph.nodes.contains_key(&SonorityGraph::start_id(Phoneme::T)));
+        let after_n: Vec<NodeData> = connections.connections[&NodeData::Phoneme(Phoneme::N)].iter().map(|(_, next)| *next).collect();
+        assert_eq!(after_n, [NodeData::Phoneme(Phoneme::T)]);
+        let after_aa = connections.connections[&NodeData::Phoneme(Phoneme::AA)].len();
+        assert_eq!(after_aa, 2);
+
+        // a syllable no other syllable can follow ends the word
+        let (_, connections) = phonology(&["T"], &["AA"], &["N"], &[], &["N T"]).compile().unwrap();
+        assert_eq!(connections.connections[&NodeData::Phoneme(Phoneme::N)], [(1, NodeData::Stop)]);
+    }
+}
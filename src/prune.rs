@@ -0,0 +1,88 @@
+use core::fmt;
+
+use crate::graph::{NodeData, NodeID, SonorityGraphEdge};
+
+/// Thresholds used to drop rare edges from a trained model.
+#[derive(Clone, Copy, Debug)]
+pub struct PruneConfig {
+    /// Edges seen fewer times than this are removed.
+    pub min_count: usize,
+    /// Edges with a smaller chance of being picked from their node than this are removed.
+    pub min_probability: f32,
+}
+
+impl Default for PruneConfig {
+    fn default() -> Self {
+        Self {
+            min_count: 2,
+            min_probability: 0.0,
+        }
+    }
+}
+
+impl PruneConfig {
+    /// Returns true if an edge seen `count` times out of `total` should be removed.
+    pub fn should_prune(&self, count: usize, total: usize) -> bool {
+        if count < self.min_count { return true };
+        total > 0 && (count as f32 / total as f32) < self.min_probability
+    }
+}
+
+
+#[derive(Clone, Debug)]
+pub enum PrunedEdge {
+    Graph(SonorityGraphEdge),
+    Connection {
+        from: NodeData,
+        to: NodeData,
+        count: usize,
+    },
+}
+
+/// Everything removed by a pruning pass.
+#[derive(Clone, Debug, Default)]
+pub struct PruneReport {
+    pub removed_edges: Vec<PrunedEdge>,
+    /// Nodes that could no longer be reached once their incoming edges were removed.
+    pub removed_nodes: Vec<NodeID>,
+}
+
+impl PruneReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn append(&mut self, mut other: PruneReport) {
+        self.removed_edges.append(&mut other.removed_edges);
+        self.removed_nodes.append(&mut other.removed_nodes);
+    }
+
+    pub fn graph_edge_count(&self) -> usize {
+        self.removed_edges.iter().filter(|edge| matches!(edge, PrunedEdge::Graph(_))).count()
+    }
+    pub fn connection_edge_count(&self) -> usize {
+        self.removed_edges.iter().filter(|edge| matches!(edge, PrunedEdge::Connection { .. })).count()
+    }
+}
+
+impl fmt::Display for PruneReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Removed {} graph edges, {} connection edges and {} nodes",
+            self.graph_edge_count(),
+            self.connection_edge_count(),
+            self.removed_nodes.len()
+        )?;
+        for edge in self.removed_edges.iter() {
+            match edge {
+                PrunedEdge::Graph(edge) => writeln!(f, "  graph: {:?} -> {:?} ({})", edge.from, edge.to, edge.count)?,
+                PrunedEdge::Connection { from, to, count } => writeln!(f, "  connection: {:?} -> {:?} ({})", from, to, count)?,
+            }
+        }
+        for node in self.removed_nodes.iter() {
+            writeln!(f, "  node: {:?}", node)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{narrow, syllable::Stress, syllablize::LexiconError, word::Word, wordgen::FakeWordGenerator};
+
+/// How closely pseudowords have to match their target word.
+#[derive(Clone, Copy, Debug)]
+pub struct MatchConfig {
+    /// How many pseudowords to find for each target.
+    pub matches: usize,
+    /// How many more or fewer phonemes a pseudoword can have than its target.
+    pub phoneme_tolerance: usize,
+    /// How far the summed biphone probability of a pseudoword can be from its target's, as a
+    /// fraction of the target's.
+    pub biphone_tolerance: f64,
+    /// How many words are generated for each target before giving up on finding more matches.
+    pub max_attempts: usize,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            matches: 1,
+            phoneme_tolerance: 0,
+            biphone_tolerance: 0.2,
+            max_attempts: 10000,
+        }
+    }
+}
+
+/// Returns true if every syllable stressed in `stresses` has a nucleus that can be stressed.
+fn can_be_stressed(word: &Word, stresses: &[Stress]) -> bool {
+    word.syllables
+        .iter()
+        .zip(stresses)
+        .filter(|(_, stress)| **stress != Stress::Unstressed)
+        .all(|(syllable, _)| !narrow::is_reduced(syllable))
+}
+
+/// Generates pseudowords for psycholinguistic experiments that match `target` the way Wuggy's
+/// do: the same number of syllables, about as many phonemes, the same stress pattern and a
+/// similar biphone probability. None of them are real words, homophones of real words or the
+/// same as each other.
+///
+/// The model doesn't know about stress, so matches are given the stress of `target`, and
+/// candidates with a reduced vowel where `target` is stressed are left out.
+///
+/// Loads the training words if needed. Returns fewer than `config.matches` pseudowords if no
+/// more were found within `config.max_attempts`.
+pub fn matched_pseudowords(generator: &mut FakeWordGenerator, target: &Word, config: &MatchConfig) -> Result<Vec<Word>, LexiconError> {
+    generator.load_real_words()?;
+    let Some(target_biphone) = generator.positional_probabilities.as_ref().map(|probabilities| probabilities.biphone_probability(target)) else { return Ok(vec![]) };
+    let allowed_biphone = target_biphone * config.biphone_tolerance;
+    let target_stresses = narrow::stresses(target);
+    let target_length = target.phonemes().len();
+
+    let mut matches: Vec<Word> = Vec::new();
+    for _ in 0..config.max_attempts {
+        if matches.len() >= config.matches { break };
+        let Some(mut candidate) = generator.generate_syllables(target.syllables.len()) else { continue };
+
+        let phonemes = candidate.phonemes();
+        if phonemes.len().abs_diff(target_length) > config.phoneme_tolerance { continue };
+        if !can_be_stressed(&candidate, &target_stresses) { continue };
+        if generator.positional_probabilities.as_ref().is_some_and(|probabilities| {
+            (probabilities.biphone_probability(&candidate) - target_biphone).abs() > allowed_biphone
+        }) { continue };
+        if generator.real_words.as_ref().is_some_and(|real_words| real_words.lexicon.contains(&candidate)) { continue };
+        if matches.iter().any(|other| other.phonemes() == phonemes) { continue };
+
+        for (syllable, stress) in candidate.syllables.iter_mut().zip(target_stresses.iter()) {
+            syllable.set_stress(*stress);
+        }
+        matches.push(candidate);
+    }
+    Ok(matches)
+}
@@ -0,0 +1,269 @@
+use crate::{inventory::Inventory, syllable::Stress};
+
+/// How stressed syllables are marked in a respelling.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StressMark {
+    None,
+    /// The syllable with primary stress is written in capitals: KIT-chuhn.
+    Capitals,
+    /// Stressed syllables start with ˈ or ˌ: ˈkit-chən.
+    Marks,
+}
+
+impl StressMark {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "capitals" | "caps" => Some(Self::Capitals),
+            "marks" => Some(Self::Marks),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RespellConfig {
+    pub stress: StressMark,
+    /// Put between syllables.
+    pub separator: String,
+}
+
+impl Default for RespellConfig {
+    fn default() -> Self {
+        Self {
+            stress: StressMark::None,
+            separator: String::from("-"),
+        }
+    }
+}
+
+
+/// A way of spelling out pronunciations with ordinary letters.
+///
+/// Schemes respell the IPA of a syllable on top of the respellings of the inventory in use, so
+/// segments a scheme doesn't know about are still spelled the inventory's way.
+pub trait RespellingScheme {
+    /// `(respelling, ipa)` pairs tried in order before the inventory's respellings.
+    fn overrides(&self) -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+
+    /// Whether the inventory's final respellings are used, like ih for a syllable ending in /ɪ/.
+    /// They only suit schemes that spell vowels the way the inventory does.
+    fn uses_final_respellings(&self) -> bool {
+        true
+    }
+
+    /// Changes the respelling of segments based on their neighbours. `segments` are the
+    /// `(ipa, respelling)` pieces of a single syllable.
+    fn adjust(&self, _segments: &mut [(&str, String)]) {}
+
+    /// The stress marking and separator the scheme is usually written with.
+    fn default_config(&self) -> RespellConfig {
+        RespellConfig::default()
+    }
+
+    /// Respells the IPA of one syllable, trying `extra` respellings after the scheme's own.
+    fn respell_syllable(&self, ipa: &str, extra: &[(&str, &str)]) -> String {
+        let overrides: Vec<(&str, &str)> = self.overrides().iter().chain(extra.iter()).copied().collect();
+        let mut segments = Inventory::current().respell_segments(ipa, &overrides, self.uses_final_respellings());
+        self.adjust(&mut segments);
+        segments.into_iter().map(|(_, respelled)| respelled).collect()
+    }
+}
+
+/// Returns a respelling scheme by name.
+pub fn scheme(name: &str) -> Option<Box<dyn RespellingScheme>> {
+    match name.to_lowercase().as_str() {
+        "wikipedia" => Some(Box::new(Wikipedia)),
+        "merriam-webster" | "mw" => Some(Box::new(MerriamWebster)),
+        "plain" | "reader-friendly" => Some(Box::new(ReaderFriendly)),
+        _ => None,
+    }
+}
+
+/// Respells a word given as the IPA of each of its syllables.
+///
+/// `extra` respellings are tried after the scheme's own, such as those of a dialect.
+pub fn respell(syllables: &[String], stresses: &[Stress], scheme: &dyn RespellingScheme, config: &RespellConfig, extra: &[(&str, &str)]) -> String {
+    syllables
+        .iter()
+        .enumerate()
+        .map(|(i, ipa)| {
+            let respelled = scheme.respell_syllable(ipa, extra);
+            let stress = match syllables.len() {
+                1 => Stress::Unstressed,
+                _ => stresses.get(i).copied().unwrap_or_default(),
+            };
+            match (config.stress, stress) {
+                (StressMark::Capitals, Stress::Primary) => respelled.to_uppercase(),
+                (StressMark::Marks, Stress::Primary) => String::from("ˈ") + &respelled,
+                (StressMark::Marks, Stress::Secondary) => String::from("ˌ") + &respelled,
+                _ => respelled,
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(&config.separator)
+}
+
+
+/// Wikipedia's pronunciation respelling key, which the inventory's respellings follow.
+///
+/// On top of the key, /tʃ/ is respelled tch after a short vowel (kitch), /ɡ/ is respelled gh
+/// where g could be read as /dʒ/ (ghet), and /s/ at the end of a syllable is respelled ss where
+/// s could be read as /z/ (eyess, tenss).
+pub struct Wikipedia;
+
+impl RespellingScheme for Wikipedia {
+    fn adjust(&self, segments: &mut [(&str, String)]) {
+        const SHORT_VOWELS: [&str; 6] = ["æ", "ɛ", "ɪ", "ɒ", "ʌ", "ʊ"];
+        const BEFORE_Z: [&str; 4] = ["n", "l", "m", "ɹ"];
+
+        for i in 0..segments.len() {
+            let prev = i.checked_sub(1).map(|j| segments[j].0);
+            let next = segments.get(i + 1).map(|segment| segment.1.clone());
+            match segments[i].0 {
+                "tʃ" if prev.is_some_and(|prev| SHORT_VOWELS.contains(&prev)) => segments[i].1 = String::from("tch"),
+                "ɡ" if next.as_deref().is_some_and(|next| next.starts_with(['e', 'i', 'y'])) => segments[i].1 = String::from("gh"),
+                "s" if next.is_none() && prev.is_some_and(|prev| {
+                    BEFORE_Z.contains(&prev) || segments[i - 1].1.ends_with(['a', 'e', 'i', 'o', 'u', 'y', 'h', 'w'])
+                }) => segments[i].1 = String::from("ss"),
+                _ => {},
+            }
+        }
+    }
+
+    fn default_config(&self) -> RespellConfig {
+        RespellConfig { stress: StressMark::Capitals, separator: String::from("-") }
+    }
+}
+
+
+/// A dictionary style respelling with diacritics, like Merriam-Webster's: ˈki-chən.
+pub struct MerriamWebster;
+
+impl RespellingScheme for MerriamWebster {
+    fn overrides(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("ir", "ɪə"),
+            ("er", "ɛə"),
+            ("u̇r", "ʊə"),
+            ("ər", "ɜː"),
+            ("ər", "ɝ"),
+            ("ər", "ɚ"),
+            ("ī", "aɪ"),
+            ("au̇", "aʊ"),
+            ("ȯi", "ɔɪ"),
+            ("ā", "eɪ"),
+            ("ō", "oʊ"),
+            ("ō", "əʊ"),
+            ("ä", "ɑː"),
+            ("ȯ", "ɔː"),
+            ("ē", "iː"),
+            ("ü", "uː"),
+            ("ᵊl", "l̩"),
+            ("ᵊm", "m̩"),
+            ("ᵊn", "n̩"),
+            ("j", "dʒ"),
+            ("ch", "tʃ"),
+            ("a", "æ"),
+            ("ä", "ɑ"),
+            ("ä", "ɒ"),
+            ("ȯ", "ɔ"),
+            ("ə", "ʌ"),
+            ("ə", "ə"),
+            ("e", "ɛ"),
+            ("ē", "i"),
+            ("i", "ɪ"),
+            ("u̇", "ʊ"),
+            ("ü", "u"),
+            ("ü", "ʉ"),
+            ("ə", "ɨ"),
+            ("t͟h", "ð"),
+            ("ŋ", "ŋ"),
+            ("y", "j"),
+        ]
+    }
+
+    fn uses_final_respellings(&self) -> bool {
+        false
+    }
+
+    fn default_config(&self) -> RespellConfig {
+        RespellConfig { stress: StressMark::Marks, separator: String::from("-") }
+    }
+}
+
+
+/// Plain letters only, spelling every vowel the same way wherever it is in a syllable: kih-chuhn.
+pub struct ReaderFriendly;
+
+impl RespellingScheme for ReaderFriendly {
+    fn overrides(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("eer", "ɪə"),
+            ("air", "ɛə"),
+            ("oor", "ʊə"),
+            ("er", "ɜː"),
+            ("er", "ɝ"),
+            ("er", "ɚ"),
+            ("ih", "ɪ"),
+            ("eh", "ɛ"),
+            ("uh", "ʌ"),
+            ("uh", "ə"),
+            ("oo", "ʊ"),
+            ("ih", "ɨ"),
+            ("th", "ð"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respell_with(syllables: &[&str], stresses: &[Stress], scheme: &dyn RespellingScheme, config: &RespellConfig) -> String {
+        let syllables: Vec<String> = syllables.iter().map(|ipa| ipa.to_string()).collect();
+        respell(&syllables, stresses, scheme, config, &[])
+    }
+
+    fn wikipedia(syllables: &[&str], stresses: &[Stress]) -> String {
+        respell_with(syllables, stresses, &Wikipedia, &Wikipedia.default_config())
+    }
+
+    #[test]
+    fn wikipedia_adjusts_ambiguous_letters() {
+        assert_eq!(wikipedia(&["kɪtʃ", "ən"], &[Stress::Primary, Stress::Unstressed]), "KITCH-uhn");
+        assert_eq!(wikipedia(&["biːtʃ"], &[Stress::Primary]), "beech");
+        assert_eq!(wikipedia(&["ɡɛt"], &[Stress::Primary]), "ghet");
+        assert_eq!(wikipedia(&["ɡɑt"], &[Stress::Primary]), "gaht");
+        assert_eq!(wikipedia(&["tɛns"], &[Stress::Primary]), "tenss");
+        assert_eq!(wikipedia(&["aɪs"], &[Stress::Primary]), "eyess");
+        assert_eq!(wikipedia(&["kæts"], &[Stress::Primary]), "kats");
+    }
+
+    #[test]
+    fn monosyllables_are_not_marked_for_stress() {
+        assert_eq!(wikipedia(&["kæt"], &[Stress::Primary]), "kat");
+        assert_eq!(respell_with(&["kæt"], &[Stress::Primary], &MerriamWebster, &MerriamWebster.default_config()), "kat");
+    }
+
+    #[test]
+    fn merriam_webster_marks_stress_and_uses_diacritics() {
+        let stresses = [Stress::Primary, Stress::Unstressed];
+        assert_eq!(respell_with(&["kɪ", "tʃən"], &stresses, &MerriamWebster, &MerriamWebster.default_config()), "ˈki-chən");
+        assert_eq!(
+            respell_with(&["ɛk", "splə", "neɪ", "ʃən"], &[Stress::Secondary, Stress::Unstressed, Stress::Primary, Stress::Unstressed], &MerriamWebster, &MerriamWebster.default_config()),
+            "ˌek-splə-ˈnā-shən"
+        );
+    }
+
+    #[test]
+    fn config_changes_stress_marks_and_separators() {
+        let stresses = [Stress::Primary, Stress::Unstressed];
+        let config = RespellConfig { stress: StressMark::Capitals, separator: String::from("·") };
+        assert_eq!(respell_with(&["kɪ", "tʃən"], &stresses, &ReaderFriendly, &config), "KIH·chuhn");
+        let config = RespellConfig { stress: StressMark::None, separator: String::from(" ") };
+        assert_eq!(respell_with(&["kɪtʃ", "ən"], &stresses, &Wikipedia, &config), "kitch uhn");
+    }
+}
@@ -0,0 +1,165 @@
+use crate::{graph::{NodeData, NodeID}, phoneme::Phoneme, syllable::Syllable, word::Word};
+
+/// How closely the stressed syllable of a word has to match to rhyme.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RhymeKind {
+    /// The same nucleus and coda after a different onset: cat, hat.
+    #[default]
+    Perfect,
+    /// A nucleus at most two features away and a coda of the same manners: cat, kit, cad.
+    Slant,
+    /// The same nucleus with any coda: cat, bad, lamp.
+    Assonance,
+}
+
+impl RhymeKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "perfect" => Some(Self::Perfect),
+            "slant" => Some(Self::Slant),
+            "assonance" => Some(Self::Assonance),
+            _ => None,
+        }
+    }
+}
+
+/// The end of a word from its stressed syllable on, to make other words rhyme with.
+///
+/// The stressed syllable rhymes the way [`RhymeKind`] describes. The syllables after it match
+/// whole: the same phonemes for a perfect rhyme, nuclei at most two features away and consonants
+/// of the same manners for a slant rhyme, and the same nuclei for assonance.
+#[derive(Clone, Debug)]
+pub struct Rhyme {
+    pub kind: RhymeKind,
+    /// The onset, nucleus and coda of the stressed syllable and of each syllable after it.
+    pub syllables: Vec<(Vec<Phoneme>, Vec<Phoneme>, Vec<Phoneme>)>,
+}
+
+impl Rhyme {
+    /// Returns none if `word` has no syllables or a syllable of the rhyme has no nucleus.
+    pub fn new(word: &Word, kind: RhymeKind) -> Option<Self> {
+        if word.syllables.is_empty() { return None };
+        let syllables = word.syllables[word.stressed_syllable()..]
+            .iter()
+            .map(|syllable| syllable.split())
+            .collect::<Option<_>>()?;
+        Some(Self { kind, syllables })
+    }
+
+    /// The number of syllables the rhyme takes up, the stressed one first.
+    pub fn len(&self) -> usize {
+        self.syllables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.syllables.is_empty()
+    }
+
+    fn allows_nucleus(&self, target: &[Phoneme], i: usize, phoneme: Phoneme) -> bool {
+        let Some(target) = target.get(i) else { return false };
+        match self.kind {
+            RhymeKind::Slant => target.features().difference(&phoneme.features()) <= 2,
+            RhymeKind::Perfect | RhymeKind::Assonance => *target == phoneme,
+        }
+    }
+
+    fn allows_consonant(&self, target: &[Phoneme], i: usize, phoneme: Phoneme) -> bool {
+        match self.kind {
+            RhymeKind::Perfect => target.get(i) == Some(&phoneme),
+            RhymeKind::Slant => target.get(i).is_some_and(|target| target.features().manner == phoneme.features().manner),
+            RhymeKind::Assonance => true,
+        }
+    }
+
+    /// Returns whether syllable `index` of the rhyme, being generated, can move on to `next` and
+    /// still rhyme, for steering [`SonorityGraph`](crate::graph::SonorityGraph) towards rhyming
+    /// syllables.
+    pub fn allows_next(&self, index: usize, syllable: &Syllable, next: NodeID) -> bool {
+        self.allows(index, syllable.phonemes(), next.data)
+    }
+
+    fn allows(&self, index: usize, phonemes: &[Phoneme], next: NodeData) -> bool {
+        let Some((target_onset, target_nucleus, target_coda)) = self.syllables.get(index) else { return false };
+        let onset = phonemes.iter().take_while(|phone| !phone.is_syllabic()).count();
+        let nucleus = phonemes[onset..].iter().take_while(|phone| phone.is_syllabic()).count();
+        let coda = phonemes.len() - onset - nucleus;
+        // the onset of the stressed syllable is free, and the consonants of assonance are too
+        let free_onset = index == 0 || self.kind == RhymeKind::Assonance;
+        let complete_onset = free_onset || onset == target_onset.len();
+        let complete_coda = self.kind == RhymeKind::Assonance || coda == target_coda.len();
+
+        match next {
+            NodeData::Stop => nucleus == target_nucleus.len() && complete_coda,
+            NodeData::Start => true,
+            NodeData::Phoneme(phoneme) if phoneme.is_syllabic() && coda == 0 => {
+                // a perfect rhyme with the same onset is the same syllable again
+                let same_onset = index == 0 && nucleus == 0 && phonemes[..onset] == target_onset[..];
+                (nucleus > 0 || complete_onset)
+                    && self.allows_nucleus(target_nucleus, nucleus, phoneme)
+                    && !(self.kind == RhymeKind::Perfect && same_onset)
+            },
+            NodeData::Phoneme(phoneme) if nucleus > 0 => nucleus == target_nucleus.len() && self.allows_consonant(target_coda, coda, phoneme),
+            NodeData::Phoneme(phoneme) => free_onset || self.allows_consonant(target_onset, onset, phoneme),
+        }
+    }
+
+    /// Returns whether the end of `word` rhymes, syllable by syllable.
+    pub fn rhymes(&self, word: &Word) -> bool {
+        let Some(first) = word.syllables.len().checked_sub(self.len()) else { return false };
+        word.syllables[first..].iter().enumerate().all(|(index, syllable)| {
+            let phonemes = syllable.phonemes();
+            (0..phonemes.len()).all(|i| self.allows(index, &phonemes[..i], NodeData::Phoneme(phonemes[i])))
+                && self.allows(index, phonemes, NodeData::Stop)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(syllables: &[&str]) -> Word {
+        Word::new(syllables.iter().map(|arpabet| Syllable::from_arpabet(arpabet).unwrap()).collect())
+    }
+
+    fn rhymes(target: &[&str], kind: RhymeKind, candidate: &[&str]) -> bool {
+        Rhyme::new(&word(target), kind).unwrap().rhymes(&word(candidate))
+    }
+
+    #[test]
+    fn perfect_rhymes_need_a_different_onset() {
+        assert!(rhymes(&["K AE1 T"], RhymeKind::Perfect, &["HH AE1 T"]));
+        assert!(rhymes(&["K AE1 T"], RhymeKind::Perfect, &["AE1 T"]));
+        assert!(!rhymes(&["K AE1 T"], RhymeKind::Perfect, &["K AE1 T"]));
+        assert!(!rhymes(&["K AE1 T"], RhymeKind::Perfect, &["HH AE1 D"]));
+        assert!(!rhymes(&["K AE1 T"], RhymeKind::Perfect, &["K IH1 T"]));
+        assert!(!rhymes(&["K AE1 T"], RhymeKind::Perfect, &["L AE1 M P"]));
+    }
+
+    #[test]
+    fn slant_rhymes_allow_similar_sounds() {
+        assert!(rhymes(&["K AE1 T"], RhymeKind::Slant, &["K IH1 T"]));
+        assert!(rhymes(&["K AE1 T"], RhymeKind::Slant, &["K AE1 D"]));
+        assert!(!rhymes(&["K AE1 T"], RhymeKind::Slant, &["K AE1 S"]));
+        assert!(!rhymes(&["K AE1 T"], RhymeKind::Slant, &["K UW1 T"]));
+    }
+
+    #[test]
+    fn assonance_only_matches_the_nucleus() {
+        assert!(rhymes(&["K AE1 T"], RhymeKind::Assonance, &["L AE1 M P"]));
+        assert!(rhymes(&["K AE1 T"], RhymeKind::Assonance, &["K AE1"]));
+        assert!(!rhymes(&["K AE1 T"], RhymeKind::Assonance, &["L IH1 M P"]));
+    }
+
+    #[test]
+    fn rhymes_start_at_the_stressed_syllable() {
+        let banana = ["B AH0", "N AE1", "N AH0"];
+        let rhyme = Rhyme::new(&word(&banana), RhymeKind::Perfect).unwrap();
+        assert_eq!(rhyme.len(), 2);
+        assert!(rhymes(&banana, RhymeKind::Perfect, &["K AH0", "B AE1", "N AH0"]));
+        assert!(rhymes(&banana, RhymeKind::Perfect, &["B AE1", "N AH0"]));
+        assert!(!rhymes(&banana, RhymeKind::Perfect, &["K AH0", "B AE1", "T AH0"]));
+        assert!(!rhymes(&banana, RhymeKind::Perfect, &["K AE1 N"]));
+        assert!(rhymes(&banana, RhymeKind::Assonance, &["K AE1", "T AH0"]));
+    }
+}
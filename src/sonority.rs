@@ -0,0 +1,109 @@
+use core::fmt;
+
+use crate::{features::{Manner, Place}, graph::{NodeData, NodeID}, phoneme::{Phoneme, SyllablePart}, syllable::Syllable, syllablize::SyllablizedPhonemes};
+
+/// A syllable margin that breaks the Sonority Sequencing Principle.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SonorityViolation {
+    /// Sonority does not rise through the onset towards the nucleus.
+    Onset(Vec<Phoneme>),
+    /// Sonority does not fall through the coda away from the nucleus.
+    Coda(Vec<Phoneme>),
+}
+
+impl fmt::Display for SonorityViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (part, cluster) = match self {
+            Self::Onset(cluster) => ("onset", cluster),
+            Self::Coda(cluster) => ("coda", cluster),
+        };
+        let ipa: String = cluster.iter().map(|phone| phone.to_ipa()).collect();
+        write!(f, "{} /{}/ breaks sonority sequencing", part, ipa)
+    }
+}
+
+
+/// Returns true for /s/ and any other voiceless alveolar fricative.
+fn is_s(phoneme: Phoneme) -> bool {
+    let features = phoneme.features();
+    !features.voiced && features.place == Some(Place::Alveolar) && features.manner == Manner::Fricative
+}
+
+/// Returns true for the coronal obstruents that can be appended to a coda, like /s z t d θ/.
+fn is_appendix(phoneme: Phoneme) -> bool {
+    let features = phoneme.features();
+    matches!(features.place, Some(Place::Alveolar | Place::Dental)) && matches!(features.manner, Manner::Stop | Manner::Fricative)
+}
+
+/// Returns true if sonority strictly rises through the onset.
+///
+/// An initial /s/ before an obstruent is exempt, as in "spr" or "sk".
+pub fn onset_is_valid(onset: &[Phoneme]) -> bool {
+    let core = match onset {
+        [first, next, ..] if is_s(*first) && next.sonority() <= first.sonority() => &onset[1..],
+        _ => onset,
+    };
+    core.windows(2).all(|pair| pair[0].sonority() < pair[1].sonority())
+}
+
+/// Returns true if sonority strictly falls through the coda.
+///
+/// A final appendix of coronal obstruents (/s z t d θ ð/) is exempt, as in "texts" or "act".
+pub fn coda_is_valid(coda: &[Phoneme]) -> bool {
+    let appendix = coda
+        .iter()
+        .rev()
+        .take_while(|phone| is_appendix(**phone))
+        .count();
+    let core = &coda[..coda.len() - appendix];
+    core.windows(2).all(|pair| pair[0].sonority() > pair[1].sonority())
+}
+
+/// Returns every margin of `syllable` that breaks the Sonority Sequencing Principle.
+pub fn check_syllable(syllable: &Syllable) -> Vec<SonorityViolation> {
+    let mut violations = Vec::new();
+    let Some((onset, _nucleus, coda)) = syllable.split() else { return violations };
+
+    if !onset_is_valid(&onset) {
+        violations.push(SonorityViolation::Onset(onset));
+    }
+    if !coda_is_valid(&coda) {
+        violations.push(SonorityViolation::Coda(coda));
+    }
+    violations
+}
+
+/// Returns every word in the training data with a syllable that breaks the Sonority Sequencing
+/// Principle, along with the index of the syllable.
+pub fn check_dictionary(syl_phones: &SyllablizedPhonemes) -> Vec<(String, usize, SonorityViolation)> {
+    let mut violations = Vec::new();
+    for (word, syllables) in syl_phones.words.iter() {
+        for (i, syl) in syllables.iter().enumerate() {
+            for violation in check_syllable(syl) {
+                violations.push((word.clone(), i, violation));
+            }
+        }
+    }
+    violations
+}
+
+/// Returns true if moving from the partial `syllable` to the node `next` keeps the syllable
+/// within the Sonority Sequencing Principle.
+///
+/// Meant to be used as a filter for [`SonorityGraph::evaluate_from_start_with`](crate::graph::SonorityGraph::evaluate_from_start_with).
+pub fn allows_next(syllable: &Syllable, next: NodeID) -> bool {
+    let NodeData::Phoneme(phoneme) = next.data else { return true };
+    let Some((mut onset, _nucleus, mut coda)) = syllable.split() else { return false };
+
+    match next.part {
+        SyllablePart::Onset => {
+            onset.push(phoneme);
+            onset_is_valid(&onset)
+        },
+        SyllablePart::Nucleus => true,
+        SyllablePart::Coda { layer: _ } => {
+            coda.push(phoneme);
+            coda_is_valid(&coda)
+        },
+    }
+}
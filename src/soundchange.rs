@@ -0,0 +1,415 @@
+use core::fmt;
+use std::{collections::HashMap, fs, io};
+
+use crate::{features::NaturalClass, phoneme::Phoneme, word::Word};
+
+#[derive(Debug)]
+pub enum SoundChangeError {
+    Io(io::Error),
+    /// A line is neither a class definition nor a rule of the form `target > replacement / environment`.
+    InvalidLine { line: usize, text: String },
+    /// Text that is not a phoneme symbol, IPA, class name or bracketed natural class.
+    UnknownSymbol { line: usize, symbol: String },
+    /// A class in the replacement has no class of the same size at the same position in the target.
+    MismatchedClass { line: usize, class: String },
+    /// `V` or `C` was used without being redefined, in an inventory with a phoneme of that symbol.
+    AmbiguousName { line: usize, name: String },
+}
+
+impl fmt::Display for SoundChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "could not read the sound changes: {}", err),
+            Self::InvalidLine { line, text } => write!(f, "line {}: cannot read {:?}", line, text),
+            Self::UnknownSymbol { line, symbol } => write!(f, "line {}: unknown phoneme or class {:?}", line, symbol),
+            Self::MismatchedClass { line, class } => write!(
+                f,
+                "line {}: {} has no class of the same size to correspond to in the target",
+                line,
+                class
+            ),
+            Self::AmbiguousName { line, name } => write!(
+                f,
+                "line {}: {} is both a phoneme and a built-in class; define the class or write the phoneme in IPA",
+                line,
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SoundChangeError {}
+
+
+/// Something a rule matches a single phoneme against.
+#[derive(Clone, Debug)]
+pub enum Segment {
+    Phoneme(Phoneme),
+    Class { name: String, phonemes: Vec<Phoneme> },
+}
+
+impl Segment {
+    pub fn matches(&self, phoneme: Phoneme) -> bool {
+        match self {
+            Self::Phoneme(expected) => *expected == phoneme,
+            Self::Class { name: _, phonemes } => phonemes.contains(&phoneme),
+        }
+    }
+}
+
+enum Token {
+    Segment(Segment),
+    /// `#`, the edge of the word.
+    Boundary,
+    /// `_`, where the target sits in the environment.
+    Focus,
+    /// `∅` or `0`, nothing.
+    Empty,
+}
+
+
+/// A rule like `k > tʃ / _i`: every `target` between `before` and `after` becomes `replacement`.
+///
+/// A class in the replacement maps each phoneme of the class at the same position in the target
+/// to the phoneme at the same index, so with `P = p t k` and `B = b d ɡ`, `P > B` voices stops.
+#[derive(Clone, Debug)]
+pub struct SoundChange {
+    /// The rule as written.
+    pub text: String,
+    pub target: Vec<Segment>,
+    pub replacement: Vec<Segment>,
+    pub before: Vec<Segment>,
+    pub after: Vec<Segment>,
+    /// The environment begins with `#`.
+    pub word_initial: bool,
+    /// The environment ends with `#`.
+    pub word_final: bool,
+}
+
+impl SoundChange {
+    fn matches_at(&self, phonemes: &[Phoneme], i: usize) -> bool {
+        let end = i + self.target.len();
+        if end > phonemes.len() || i < self.before.len() { return false };
+        let after_end = end + self.after.len();
+        if after_end > phonemes.len() { return false };
+
+        let start = i - self.before.len();
+        if self.word_initial && start != 0 { return false };
+        if self.word_final && after_end != phonemes.len() { return false };
+
+        let segments = self.before.iter().chain(self.target.iter()).chain(self.after.iter());
+        segments.zip(phonemes[start..after_end].iter()).all(|(segment, phoneme)| segment.matches(*phoneme))
+    }
+
+    fn replace(&self, matched: &[Phoneme]) -> Vec<Phoneme> {
+        self.replacement
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| match (segment, self.target.get(i)) {
+                (Segment::Phoneme(phoneme), _) => *phoneme,
+                (Segment::Class { name: _, phonemes }, Some(Segment::Class { name: _, phonemes: targets })) => {
+                    let index = targets.iter().position(|target| *target == matched[i]).unwrap();
+                    phonemes[index]
+                },
+                // ruled out when the rule is parsed
+                (Segment::Class { .. }, _) => unreachable!(),
+            })
+            .collect()
+    }
+
+    /// Applies the rule everywhere it matches at once, scanning left to right. Matches don't
+    /// overlap, and the environment is always checked against the phonemes before the change.
+    pub fn apply(&self, phonemes: &[Phoneme]) -> Vec<Phoneme> {
+        let mut result = Vec::new();
+        let mut i = 0;
+
+        while i <= phonemes.len() {
+            if self.matches_at(phonemes, i) {
+                let end = i + self.target.len();
+                result.extend(self.replace(&phonemes[i..end]));
+                if end > i {
+                    i = end;
+                    continue;
+                }
+            }
+            if let Some(phoneme) = phonemes.get(i) {
+                result.push(*phoneme);
+            }
+            i += 1;
+        }
+
+        result
+    }
+}
+
+impl fmt::Display for SoundChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+
+/// A rule that changed a word, and the word before and after it.
+#[derive(Clone, Debug)]
+pub struct AppliedChange {
+    pub rule: String,
+    pub before: Vec<Phoneme>,
+    pub after: Vec<Phoneme>,
+}
+
+impl fmt::Display for AppliedChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let before: String = self.before.iter().map(|phone| phone.to_ipa()).collect();
+        let after: String = self.after.iter().map(|phone| phone.to_ipa()).collect();
+        write!(f, "{}: /{}/ > /{}/", self.rule, before, after)
+    }
+}
+
+
+/// Every rule that fired on a word, in order.
+#[derive(Clone, Debug, Default)]
+pub struct SoundChangeTrace {
+    pub applied: Vec<AppliedChange>,
+}
+
+impl fmt::Display for SoundChangeTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.applied.iter() {
+            writeln!(f, "  {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// An ordered list of sound changes, read from a plain text file.
+///
+/// Each line is either a class definition like `N = m n ŋ` or a rule like `k > tʃ / _i`. Phonemes
+/// may be written with their symbol or IPA, and natural classes in brackets like `[voiceless stops]`.
+/// `V` and `C` are vowels and consonants unless redefined, and can't be used without being
+/// redefined if the inventory has a phoneme of that symbol, like English `V`. `#` is the edge of the word and `∅`
+/// or `0` is nothing, for deletions and insertions. Anything after `//` is a comment.
+#[derive(Clone, Debug)]
+pub struct SoundChanges {
+    pub classes: HashMap<String, Vec<Phoneme>>,
+    pub rules: Vec<SoundChange>,
+    /// Built-in class names that are also phoneme symbols and haven't been redefined.
+    ambiguous: Vec<String>,
+}
+
+impl SoundChanges {
+    pub fn load(path: &str) -> Result<Self, SoundChangeError> {
+        let contents = fs::read_to_string(path).map_err(SoundChangeError::Io)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, SoundChangeError> {
+        let mut changes = Self { classes: HashMap::new(), rules: Vec::new(), ambiguous: Vec::new() };
+        for (name, class) in [("V", NaturalClass::vowels()), ("C", NaturalClass::consonants())] {
+            match Phoneme::try_from_arpabet(name) {
+                Some(_) => changes.ambiguous.push(name.to_owned()),
+                None => { changes.classes.insert(name.to_owned(), class.phonemes()); },
+            }
+        }
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let text = line.split("//").next().unwrap().trim();
+            if text.is_empty() { continue };
+            let invalid = || SoundChangeError::InvalidLine { line: line_number, text: text.to_owned() };
+
+            if let Some((target, rest)) = text.split_once('>').or_else(|| text.split_once('→')) {
+                let (replacement, environment) = rest.split_once('/').unwrap_or((rest, "_"));
+                let (before, after) = environment.split_once('_').ok_or_else(invalid)?;
+
+                let target = changes.segments(target, line_number)?;
+                let replacement = changes.segments(replacement, line_number)?;
+
+                let mut before = changes.tokenize(before, line_number)?;
+                let word_initial = matches!(before.first(), Some(Token::Boundary));
+                if word_initial { before.remove(0); }
+                let mut after = changes.tokenize(after, line_number)?;
+                let word_final = matches!(after.last(), Some(Token::Boundary));
+                if word_final { after.pop(); }
+
+                let rule = SoundChange {
+                    text: text.to_owned(),
+                    target,
+                    replacement,
+                    before: Self::only_segments(before).ok_or_else(invalid)?,
+                    after: Self::only_segments(after).ok_or_else(invalid)?,
+                    word_initial,
+                    word_final,
+                };
+
+                for (i, segment) in rule.replacement.iter().enumerate() {
+                    let Segment::Class { name, phonemes } = segment else { continue };
+                    let corresponds = matches!(rule.target.get(i), Some(Segment::Class { name: _, phonemes: targets }) if targets.len() == phonemes.len());
+                    if !corresponds {
+                        return Err(SoundChangeError::MismatchedClass { line: line_number, class: name.clone() });
+                    }
+                }
+                changes.rules.push(rule);
+            } else if let Some((name, members)) = text.split_once('=') {
+                let name = name.trim();
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(invalid());
+                }
+
+                let mut phonemes = Vec::new();
+                for segment in changes.segments(members, line_number)? {
+                    match segment {
+                        Segment::Phoneme(phoneme) => phonemes.push(phoneme),
+                        Segment::Class { name: _, phonemes: members } => phonemes.extend(members),
+                    }
+                }
+                changes.ambiguous.retain(|ambiguous| ambiguous != name);
+                changes.classes.insert(name.to_owned(), phonemes);
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(changes)
+    }
+
+    fn only_segments(tokens: Vec<Token>) -> Option<Vec<Segment>> {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                Token::Segment(segment) => Some(segment),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Reads a target or replacement, where `∅` stands for nothing.
+    fn segments(&self, text: &str, line: usize) -> Result<Vec<Segment>, SoundChangeError> {
+        let mut segments = Vec::new();
+        for token in self.tokenize(text, line)? {
+            match token {
+                Token::Segment(segment) => segments.push(segment),
+                Token::Empty => {},
+                _ => return Err(SoundChangeError::InvalidLine { line, text: text.trim().to_owned() }),
+            }
+        }
+        Ok(segments)
+    }
+
+    /// Splits text into phonemes and classes, always reading the longest name that fits so
+    /// that `tʃ` is read as one phoneme. Class names win over phoneme symbols of the same length.
+    fn tokenize(&self, text: &str, line: usize) -> Result<Vec<Token>, SoundChangeError> {
+        let mut tokens = Vec::new();
+        let mut rest = text.trim_start();
+
+        while let Some(first) = rest.chars().next() {
+            let (token, len) = match first {
+                '#' => (Token::Boundary, 1),
+                '_' => (Token::Focus, 1),
+                '∅' | '0' => (Token::Empty, first.len_utf8()),
+                '[' => {
+                    let end = rest.find(']').ok_or(SoundChangeError::InvalidLine { line, text: text.trim().to_owned() })?;
+                    let description = &rest[1..end];
+                    let class = NaturalClass::parse(description)
+                        .ok_or(SoundChangeError::UnknownSymbol { line, symbol: description.to_owned() })?;
+                    (Token::Segment(Segment::Class { name: rest[..=end].to_owned(), phonemes: class.phonemes() }), end + 1)
+                },
+                _ => {
+                    let ends = rest.char_indices().map(|(i, c)| i + c.len_utf8()).collect::<Vec<usize>>();
+                    let found = ends.iter().rev().find_map(|end| {
+                        let name = &rest[..*end];
+                        let segment = match self.classes.get(name) {
+                            Some(phonemes) => Segment::Class { name: name.to_owned(), phonemes: phonemes.clone() },
+                            None => Segment::Phoneme(Phoneme::try_from_arpabet(name).or_else(|| Phoneme::try_from_ipa(name))?),
+                        };
+                        Some((Token::Segment(segment), *end))
+                    });
+                    let symbol = rest.split_whitespace().next().unwrap_or(rest);
+                    let found = found.ok_or(SoundChangeError::UnknownSymbol { line, symbol: symbol.to_owned() })?;
+                    if let Some(name) = self.ambiguous.iter().find(|name| **name == rest[..found.1]) {
+                        return Err(SoundChangeError::AmbiguousName { line, name: name.clone() });
+                    }
+                    found
+                },
+            };
+            tokens.push(token);
+            rest = rest[len..].trim_start();
+        }
+
+        Ok(tokens)
+    }
+
+    /// Applies every rule in order to the phonemes of `word`, then syllabifies the result again.
+    pub fn apply(&self, word: &Word) -> (Word, SoundChangeTrace) {
+        let mut trace = SoundChangeTrace::default();
+        let mut phonemes = word.phonemes();
+
+        for rule in self.rules.iter() {
+            let changed = rule.apply(&phonemes);
+            if changed != phonemes {
+                trace.applied.push(AppliedChange { rule: rule.text.clone(), before: phonemes, after: changed.clone() });
+                phonemes = changed;
+            }
+        }
+
+        (Word::syllabify(&phonemes), trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phonemes(arpabet: &str) -> Vec<Phoneme> {
+        arpabet.split_whitespace().map(|symbol| Phoneme::try_from_arpabet(symbol).unwrap()).collect()
+    }
+
+    /// Applies the rules in `contents` to a word written in ARPAbet.
+    fn apply(contents: &str, arpabet: &str) -> Vec<Phoneme> {
+        let changes = SoundChanges::parse(contents).unwrap();
+        changes.rules.iter().fold(phonemes(arpabet), |word, rule| rule.apply(&word))
+    }
+
+    #[test]
+    fn tokens_are_the_longest_phoneme_or_class() {
+        assert_eq!(apply("tʃ > k", "CH IY Z"), phonemes("K IY Z"));
+        assert_eq!(apply("AXR > ER", "B AX T AXR"), phonemes("B AX T ER"));
+        assert_eq!(apply("N = m n\nN > P", "N M NG"), phonemes("P P NG"));
+        assert_eq!(apply("[nasal] > ∅ / _#", "K AE N"), phonemes("K AE"));
+        assert!(matches!(SoundChanges::parse("[nasal > ∅"), Err(SoundChangeError::InvalidLine { line: 1, .. })));
+        assert!(matches!(SoundChanges::parse("[flying] > ∅"), Err(SoundChangeError::UnknownSymbol { line: 1, .. })));
+        assert!(matches!(SoundChanges::parse("// a comment\nX > Y"), Err(SoundChangeError::UnknownSymbol { line: 2, .. })));
+    }
+
+    #[test]
+    fn boundaries_anchor_rules_to_the_edges_of_the_word() {
+        assert_eq!(apply("K > G / #_", "K AE K"), phonemes("G AE K"));
+        assert_eq!(apply("K > G / _#", "K AE K"), phonemes("K AE G"));
+        assert_eq!(apply("K > G / AE_", "K AE K"), phonemes("K AE G"));
+    }
+
+    #[test]
+    fn rules_insert_and_delete() {
+        assert_eq!(apply("T > 0 / _#", "K AE T"), phonemes("K AE"));
+        assert_eq!(apply("T > ∅", "T AE T"), phonemes("AE"));
+        assert_eq!(apply("∅ > AX / #_S T", "S T AA P"), phonemes("AX S T AA P"));
+        assert_eq!(apply("0 > AX / S_T", "S T AA P"), phonemes("S AX T AA P"));
+    }
+
+    #[test]
+    fn classes_replace_classes_by_position() {
+        let rules = "P = p t k\nB = b d ɡ\nP > B / [vowel]_[vowel]";
+        assert_eq!(apply(rules, "AE T AX"), phonemes("AE D AX"));
+        assert_eq!(apply(rules, "AE K AX P"), phonemes("AE G AX P"));
+        assert!(matches!(SoundChanges::parse("P = p t k\nN = m n\nP > N"), Err(SoundChangeError::MismatchedClass { line: 3, .. })));
+        assert!(matches!(SoundChanges::parse("N = m n\nAE > N"), Err(SoundChangeError::MismatchedClass { line: 2, .. })));
+    }
+
+    #[test]
+    fn built_in_classes_never_shadow_phonemes() {
+        assert!(matches!(SoundChanges::parse("V > F"), Err(SoundChangeError::AmbiguousName { line: 1, .. })));
+        assert_eq!(apply("v > f", "V AE N"), phonemes("F AE N"));
+        assert_eq!(apply("V = [vowel]\nV > ∅ / C_#", "S OW D AX"), phonemes("S OW D"));
+        assert_eq!(apply("C > ∅ / _#", "K AE T"), phonemes("K AE"));
+    }
+}
@@ -0,0 +1,313 @@
+use core::fmt;
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{connections::SyllableConnections, graph::{NodeData, NodeID, SonorityGraph}, phoneme::{Phoneme, SyllablePart}, syllablize::SyllablizedPhonemes};
+
+/// Options for [`ModelStats::compute`].
+#[derive(Clone, Copy, Debug)]
+pub struct StatsConfig {
+    /// How many clusters and nodes to list in each ranking.
+    pub top: usize,
+    /// The largest syllable count to estimate the number of distinct words for.
+    pub max_syllables: usize,
+    /// The longest syllable, in phonemes, counted when estimating the number of distinct words.
+    pub max_syllable_length: usize,
+}
+
+impl Default for StatsConfig {
+    fn default() -> Self {
+        Self {
+            top: 10,
+            max_syllables: 4,
+            max_syllable_length: 8,
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PhonemeUsage {
+    pub onset: usize,
+    pub nucleus: usize,
+    pub coda: usize,
+}
+
+impl PhonemeUsage {
+    pub fn total(&self) -> usize {
+        self.onset + self.nucleus + self.coda
+    }
+}
+
+/// How spread out the choices leaving a node are.
+#[derive(Clone, Copy, Debug)]
+pub struct BranchStats<T> {
+    pub node: T,
+    /// The number of distinct edges leaving the node.
+    pub branching: usize,
+    /// The Shannon entropy, in bits, of the choice of edge.
+    pub entropy: f32,
+}
+
+/// A summary of a trained model.
+#[derive(Clone, Debug)]
+pub struct ModelStats {
+    pub word_count: usize,
+    pub syllable_count: usize,
+    /// Sorted from most to least used.
+    pub phoneme_usage: Vec<(Phoneme, PhonemeUsage)>,
+    pub top_onsets: Vec<(Vec<Phoneme>, usize)>,
+    pub top_nuclei: Vec<(Vec<Phoneme>, usize)>,
+    pub top_codas: Vec<(Vec<Phoneme>, usize)>,
+    /// Sorted from highest to lowest entropy.
+    pub nodes: Vec<BranchStats<NodeID>>,
+    /// Sorted from highest to lowest entropy.
+    pub connections: Vec<BranchStats<NodeData>>,
+    /// The estimated number of distinct words with each number of syllables, starting at one.
+    pub distinct_words: Vec<f64>,
+    top: usize,
+}
+
+pub fn entropy(counts: impl Iterator<Item = usize> + Clone) -> f32 {
+    let total: usize = counts.clone().sum();
+    if total == 0 { return 0.0 };
+    counts
+        .filter(|count| *count > 0)
+        .map(|count| {
+            let p = count as f32 / total as f32;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn top_clusters(counts: HashMap<Vec<Phoneme>, usize>, top: usize) -> Vec<(Vec<Phoneme>, usize)> {
+    let mut clusters: Vec<(Vec<Phoneme>, usize)> = counts.into_iter().collect();
+    clusters.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.len().cmp(&b.0.len())));
+    clusters.truncate(top);
+    clusters
+}
+
+fn format_cluster(cluster: &[Phoneme]) -> String {
+    if cluster.is_empty() { return String::from("(none)") };
+    let ipa: String = cluster.iter().map(|phone| phone.to_ipa()).collect();
+    format!("/{}/", ipa)
+}
+
+impl ModelStats {
+    pub fn compute(syl_phones: &SyllablizedPhonemes, graph: &SonorityGraph, connections: &SyllableConnections, config: &StatsConfig) -> Self {
+        let mut usage: HashMap<Phoneme, PhonemeUsage> = HashMap::new();
+        let mut onsets = HashMap::new();
+        let mut nuclei = HashMap::new();
+        let mut codas = HashMap::new();
+        let mut syllable_count = 0;
+
+        for (_word, syllables) in syl_phones.words.iter() {
+            for syl in syllables.iter() {
+                let Some((onset, nucleus, coda)) = syl.split() else { continue };
+                syllable_count += 1;
+
+                for phone in onset.iter() {
+                    usage.entry(*phone).or_default().onset += 1;
+                }
+                for phone in nucleus.iter() {
+                    usage.entry(*phone).or_default().nucleus += 1;
+                }
+                for phone in coda.iter() {
+                    usage.entry(*phone).or_default().coda += 1;
+                }
+
+                *onsets.entry(onset).or_insert(0) += 1;
+                *nuclei.entry(nucleus).or_insert(0) += 1;
+                *codas.entry(coda).or_insert(0) += 1;
+            }
+        }
+
+        let mut phoneme_usage: Vec<(Phoneme, PhonemeUsage)> = usage.into_iter().collect();
+        phoneme_usage.sort_by_key(|usage| Reverse(usage.1.total()));
+
+        let mut nodes: Vec<BranchStats<NodeID>> = graph.nodes
+            .iter()
+            .filter(|(_id, node)| !node.outs.is_empty())
+            .map(|(id, node)| BranchStats {
+                node: *id,
+                branching: node.outs.len(),
+                entropy: entropy(node.outs.iter().map(|edge| edge.count)),
+            })
+            .collect();
+        nodes.sort_by(|a, b| b.entropy.total_cmp(&a.entropy));
+
+        let mut conns: Vec<BranchStats<NodeData>> = connections.connections
+            .iter()
+            .map(|(from, edges)| BranchStats {
+                node: *from,
+                branching: edges.len(),
+                entropy: entropy(edges.iter().map(|edge| edge.0)),
+            })
+            .collect();
+        conns.sort_by(|a, b| b.entropy.total_cmp(&a.entropy));
+
+        Self {
+            word_count: syl_phones.words.len(),
+            syllable_count,
+            phoneme_usage,
+            top_onsets: top_clusters(onsets, config.top),
+            top_nuclei: top_clusters(nuclei, config.top),
+            top_codas: top_clusters(codas, config.top),
+            nodes,
+            connections: conns,
+            distinct_words: estimate_distinct_words(graph, connections, config),
+            top: config.top,
+        }
+    }
+
+    /// The average branching factor and entropy of the nodes in each syllable part.
+    pub fn part_averages(&self) -> Vec<(SyllablePart, f32, f32)> {
+        let mut sums: HashMap<SyllablePart, (usize, f32, usize)> = HashMap::new();
+        for node in self.nodes.iter() {
+            let part = match node.node.part {
+                SyllablePart::Coda { layer: _ } => SyllablePart::Coda { layer: 1 },
+                part => part,
+            };
+            let sum = sums.entry(part).or_insert((0, 0.0, 0));
+            sum.0 += node.branching;
+            sum.1 += node.entropy;
+            sum.2 += 1;
+        }
+
+        [SyllablePart::Onset, SyllablePart::Nucleus, SyllablePart::Coda { layer: 1 }]
+            .into_iter()
+            .filter_map(|part| {
+                let (branching, entropy, n) = sums.get(&part)?;
+                Some((part, *branching as f32 / *n as f32, entropy / *n as f32))
+            })
+            .collect()
+    }
+}
+
+/// Counts the ways a syllable starting at `id` can be finished in at most `depth` more phonemes,
+/// grouped by the last phoneme of the syllable.
+fn count_syllables(
+    graph: &SonorityGraph,
+    id: NodeID,
+    depth: usize,
+    memo: &mut HashMap<(NodeID, usize), HashMap<Phoneme, f64>>,
+) -> HashMap<Phoneme, f64> {
+    if let Some(counts) = memo.get(&(id, depth)) {
+        return counts.clone();
+    }
+
+    let mut counts = HashMap::new();
+    if let (Some(node), NodeData::Phoneme(phoneme)) = (graph.get_node(id), id.data) {
+        for edge in node.outs.iter() {
+            if edge.to.data == NodeData::Stop {
+                *counts.entry(phoneme).or_insert(0.0) += 1.0;
+            } else if depth > 0 {
+                for (last, count) in count_syllables(graph, edge.to, depth - 1, memo) {
+                    *counts.entry(last).or_insert(0.0) += count;
+                }
+            }
+        }
+    }
+
+    memo.insert((id, depth), counts.clone());
+    counts
+}
+
+/// Estimates how many distinct words the model can produce for each syllable count by counting
+/// every path through the connections and the graph. Different paths can spell out the same
+/// phonemes, so this is an upper bound.
+pub fn estimate_distinct_words(graph: &SonorityGraph, connections: &SyllableConnections, config: &StatsConfig) -> Vec<f64> {
+    let mut memo = HashMap::new();
+    let mut syllables: HashMap<Phoneme, HashMap<Phoneme, f64>> = HashMap::new();
+
+    let mut syllables_from = |data: NodeData| -> HashMap<Phoneme, f64> {
+        let NodeData::Phoneme(start) = data else { return HashMap::new() };
+        // no syllable fits in zero phonemes
+        let Some(depth) = config.max_syllable_length.checked_sub(1) else { return HashMap::new() };
+        syllables
+            .entry(start)
+            .or_insert_with(|| count_syllables(graph, SonorityGraph::start_id(start), depth, &mut memo))
+            .clone()
+    };
+
+    let mut estimates = Vec::new();
+    let mut current: HashMap<NodeData, f64> = HashMap::from([(NodeData::Start, 1.0)]);
+
+    for _ in 0..config.max_syllables {
+        let mut next: HashMap<NodeData, f64> = HashMap::new();
+        for (from, ways) in current.iter() {
+            let Some(edges) = connections.connections.get(from) else { continue };
+            for (_count, to) in edges.iter() {
+                for (last, count) in syllables_from(*to) {
+                    *next.entry(NodeData::Phoneme(last)).or_insert(0.0) += ways * count;
+                }
+            }
+        }
+        estimates.push(next.values().sum());
+        current = next;
+    }
+
+    estimates
+}
+
+impl fmt::Display for ModelStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Training data: {} words, {} syllables", self.word_count, self.syllable_count)?;
+
+        writeln!(f, "\nPhoneme usage (onset / nucleus / coda):")?;
+        for (phoneme, usage) in self.phoneme_usage.iter() {
+            writeln!(f, "  {:<4} /{}/\t{:>8} {:>8} {:>8}", phoneme.to_arpabet(), phoneme.to_ipa(), usage.onset, usage.nucleus, usage.coda)?;
+        }
+
+        for (name, clusters) in [("onsets", &self.top_onsets), ("nuclei", &self.top_nuclei), ("codas", &self.top_codas)] {
+            writeln!(f, "\nMost frequent {}:", name)?;
+            for (cluster, count) in clusters.iter() {
+                writeln!(f, "  {:<12} {:>8}", format_cluster(cluster), count)?;
+            }
+        }
+
+        writeln!(f, "\nSonority graph: {} nodes with outgoing edges", self.nodes.len())?;
+        for (part, branching, entropy) in self.part_averages() {
+            writeln!(f, "  {:?}: average branching {:.2}, average entropy {:.2} bits", part, branching, entropy)?;
+        }
+        writeln!(f, "  Highest entropy nodes:")?;
+        for node in self.nodes.iter().take(self.top) {
+            writeln!(f, "    {:?} {:?}: branching {}, entropy {:.2} bits", node.node.part, node.node.data, node.branching, node.entropy)?;
+        }
+
+        writeln!(f, "\nSyllable connections: {} phonemes end a syllable", self.connections.len())?;
+        for conn in self.connections.iter().take(self.top) {
+            writeln!(f, "  {:?}: branching {}, entropy {:.2} bits", conn.node, conn.branching, conn.entropy)?;
+        }
+
+        writeln!(f, "\nEstimated distinct words:")?;
+        for (i, count) in self.distinct_words.iter().enumerate() {
+            writeln!(f, "  {} syllable(s): {:.3e}", i + 1, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phonology::Phonology;
+
+    #[test]
+    fn estimates_are_empty_without_room_for_a_syllable() {
+        let phonology = Phonology {
+            inventory: None,
+            onsets: vec![(String::from("T"), 1), (String::from("S"), 1)],
+            nuclei: vec![(String::from("AA"), 1)],
+            codas: vec![],
+            templates: vec![],
+            forbidden: vec![],
+        };
+        let (graph, connections) = phonology.compile().unwrap();
+
+        let config = StatsConfig { max_syllables: 2, ..Default::default() };
+        assert_eq!(estimate_distinct_words(&graph, &connections, &config), [2.0, 4.0]);
+        let config = StatsConfig { max_syllables: 2, max_syllable_length: 0, ..Default::default() };
+        assert_eq!(estimate_distinct_words(&graph, &connections, &config), [0.0, 0.0]);
+    }
+}
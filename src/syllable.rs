@@ -1,127 +1,75 @@
 use std::ops::{Index, IndexMut};
 
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use crate::phoneme::{Phoneme, SyllablePart};
-
-
-const RESPELL_KEY: [[&'static str; 2]; 68]  = [
-    ["ire", "aɪər"],
-    ["oir", "ɔɪər"],
-    ["our", "aʊər"],
-    ["eer", "ɪər"],
-    ["air", "ɛər"],
-    ["ure", "jʊər"],
-    ["ur", "ɜːr"],
-    ["ew", "juː"],
-    ["eye", "aɪ"],
-    ["err", "ɛr"],
-    ["irr", "ɪr"],
-    ["urr", "ʌr"],
-    ["uurr", "ʊr"],
-    ["uhr", "ər"],
-    ["oor", "ʊər"],
-    ["or", "ɔːr"],
-    ["orr", "ɒr"],
-    ["oh", "oʊ"],
-    ["oo", "uː"],
-    ["ar", "ɑːr"],
-    ["arr", "ær"],
-    ["y", "aɪ"],
-    ["ay", "eɪ"],
-    ["ee", "iː"],
-    ["aw", "ɔː"],
-    ["ow", "aʊ"],
-    ["oy", "ɔɪ"],
-    ["ah", "ɑː"],
-    ["ah", "ɑ"],
-    ["ee", "i"],
-    ["oo", "u"],
-    ["aw", "ɔ"],
-    //   ["ə", "ə"],
-    //   ["ər", "ər"],
-    ["uh", "ə"], // use `uh` instead of ə
-    //
-    ["a", "æ"],
-    ["o", "ɒ"],
-    ["uu", "ʊ"],
-    //
-    ["i", "ɪ"],
-    ["u", "ʌ"],
-    ["e", "ɛ"],
-    //   ["ih", "ɪ$"], // ɪ at the end of a syllable is 'ih' not 'i'
-    //   ["uh", "ʌ$"], // ʌ at the end of a syllable is 'uh' not 'u'
-    //   ["eh", "ɛ$"], // ɛ at the end of a syllable is 'eh' not 'e'
-    //
-    ["j", "dʒ"],
-    ["nk", "ŋk"],
-    ["wh", "hw"],
-    ["b", "b"],
-    ["ch", "tʃ"],
-    ["d", "d"],
-    ["dh", "ð"],
-    ["f", "f"],
-    ["g", "ɡ"],
-    //   ["gh", "ɡ"], //  IGNORED: /ɡ/ may be respelled gh instead of g when otherwise it may be misinterpreted as /dʒ/.
-    //   ["tch", "tʃ"], // IGNORED: /tʃ/ after a vowel in the same syllable is respelled tch instead of ch to better distinguish it from /k, x/.
-    ["h", "h"],
-    ["k", "k"],
-    ["kh", "x"],
-    ["l", "l"],
-    ["l", "ɫ"],
-    ["m", "m"],
-    ["n", "n"],
-    ["ng", "ŋ"],
-    ["p", "p"],
-    ["r", "ɹ"],
-    ["r", "r"],
-    ["s", "s"],
-    //   ["ss", "s"], // /s/ may be respelled ss instead of s when otherwise it may be misinterpreted as /z/: "ice" EYESS, "tense" TENSS (compare eyes, tens).
-    ["sh", "ʃ"],
-    ["t", "t"],
-    ["th", "θ"],
-    ["v", "v"],
-    ["w", "w"],
-    ["y", "j"],
-    ["z", "z"],
-    ["zh", "ʒ"],
-];
-
-
-const SPECIAL_ENDERS: [[&'static str; 2]; 3] = [
-  ["ih", "ɪ"], // ɪ at the end of a syllable is 'ih' not 'i'
-  ["uh", "ʌ"], // ʌ at the end of a syllable is 'uh' not 'u'
-  ["eh", "ɛ"], // ɛ at the end of a syllable is 'eh' not 'e'
-];
+use crate::{notation::Notation, phoneme::{Phoneme, SyllablePart}, respell::{RespellingScheme, Wikipedia}};
+
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Stress {
+    #[default]
+    Unstressed,
+    Primary,
+    Secondary,
+}
+
+impl Stress {
+    /// Reads a lexicon stress digit: 1 is primary stress and 2 is secondary stress. 
+    pub fn from_digit(digit: char) -> Self {
+        match digit {
+            '1' => Self::Primary,
+            '2' => Self::Secondary,
+            _ => Self::Unstressed,
+        }
+    }
+}
 
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Syllable {
     phonemes: Vec<Phoneme>,
+    /// Only known for syllables read from a lexicon. 
+    #[serde(default)]
+    stress: Stress,
 }
 
 impl Syllable {
     pub fn new(phonemes: Vec<Phoneme>) -> Self {
-        Self { phonemes }
+        Self { phonemes, stress: Stress::Unstressed }
     }
     pub fn empty() -> Self {
-        Self { phonemes: vec![] }
+        Self { phonemes: vec![], stress: Stress::Unstressed }
     }
 
     pub fn from_phonemes(phonemes: Vec<Phoneme>) -> Self {
-        Self { phonemes }
+        Self { phonemes, stress: Stress::Unstressed }
     }
 
-    pub fn from_arpabet(arpabet: &str) -> Self {
+    /// Parses space separated symbols of the inventory in use. Stress is read from the digits 
+    /// after the symbols, as in `K AE1 T`. 
+    /// 
+    /// Returns the first symbol that is not in the inventory if there is one. 
+    pub fn from_arpabet(arpabet: &str) -> Result<Self, String> {
         let mut phonemes = Vec::new();
-        for phoneme in arpabet.split(' ') {
-            if let Some(caps) = Regex::new(r"^([A-Z]+)\d*$").unwrap().captures(phoneme) {
-                let phone = Phoneme::from_arpabet(caps.get(1).unwrap().as_str());
-                phonemes.push(phone);
+        let mut stress = Stress::Unstressed;
+        for phoneme in arpabet.split_whitespace() {
+            let symbol = phoneme.trim_end_matches(|c: char| c.is_ascii_digit());
+            if symbol.is_empty() { continue };
+            if let Some(digit) = phoneme[symbol.len()..].chars().next() {
+                if stress == Stress::Unstressed {
+                    stress = Stress::from_digit(digit);
+                }
             }
+            phonemes.push(Phoneme::try_from_arpabet(symbol).ok_or_else(|| symbol.to_owned())?);
         }
-        Syllable::from_phonemes(phonemes)
+        Ok(Syllable { phonemes, stress })
+    }
+
+    pub fn stress(&self) -> Stress {
+        self.stress
+    }
+    pub fn set_stress(&mut self, stress: Stress) {
+        self.stress = stress;
     }
 
     pub fn to_ipa(&self) -> String {
@@ -132,6 +80,10 @@ impl Syllable {
         result
     }
 
+    pub fn to_notation(&self, notation: Notation) -> String {
+        notation.write(&self.to_ipa())
+    }
+
     pub fn to_arpabet(&self) -> String {
         let mut result = String::new();
         for phoneme in self.phonemes.iter() {
@@ -141,43 +93,22 @@ impl Syllable {
         result
     }
     
+    /// Respells the syllable with the Wikipedia respelling key. 
     pub fn to_english(&self) -> String {
-        let mut result = String::new();
-        let mut ipa = self.to_ipa();
-
-        while ipa.chars().count() > 0 {
-            let mut found_any = false;
-            for [replace, check] in RESPELL_KEY {
-                let ender = SPECIAL_ENDERS.iter().position(|[_, end]| *end == ipa);
-                
-                if let Some(end) = ender {
-                    let eng_end = SPECIAL_ENDERS[end][0];
-                    result += eng_end;
-                    ipa = String::from("");
-                    found_any = true;
-                    break;
-                }
-
-                if ipa.starts_with(check) {
-                    result += replace;
-                    ipa = ipa[check.len()..].to_string();
-                    found_any = true;
-                    break;
-                }
-            };
-
-            if !found_any {
-                result += &ipa.chars().nth(0).unwrap().to_string();
-                ipa.remove(0);
-            }
-        };
+        Wikipedia.respell_syllable(&self.to_ipa(), &[])
+    }
 
-        result
+    pub fn phonemes(&self) -> &[Phoneme] {
+        &self.phonemes
     }
 
     pub fn add_phoneme(&mut self, phoneme: Phoneme) {
         self.phonemes.push(phoneme);
     }
+
+    pub fn pop_phoneme(&mut self) -> Option<Phoneme> {
+        self.phonemes.pop()
+    }
     
     pub fn last_phoneme(&self) -> Phoneme {
         self.phonemes.last().unwrap().clone()
@@ -190,45 +121,44 @@ impl Syllable {
     /// Returns a tuple where the first element is the onset, 
     /// the second is the nucleus, and the third is the coda. 
     /// 
-    /// Returns none if the syllable is invalid (the coda has a vowel in it or there is no vowel at all). 
+    /// The nucleus is made of syllabic phonemes: vowels, including r-colored vowels, and the syllabic consonants. 
+    /// 
+    /// Returns none if the syllable is invalid (the coda has a syllabic phoneme in it or there is no nucleus at all). 
     pub fn split(&self) -> Option<(Vec<Phoneme>, Vec<Phoneme>, Vec<Phoneme>)> {
         let mut onset = Vec::new();
-        let mut vowel = Vec::new();
+        let mut nucleus = Vec::new();
         let mut coda = Vec::new();
         let mut state = SyllablePart::Onset;
 
         for phone in self.phonemes.iter() {
             if state == SyllablePart::Onset {
-                if phone.is_consonant() {
-                    onset.push(phone.clone());
-                } else if phone.is_vowel() {
-                    vowel.push(phone.clone());
+                if phone.is_syllabic() {
+                    nucleus.push(*phone);
                     state = SyllablePart::Nucleus;
-                    continue;
                 } else {
-                    return None;
+                    onset.push(*phone);
                 }
             } else if state == SyllablePart::Nucleus {
-                if phone.is_vowel() {
-                    vowel.push(phone.clone());
+                if phone.is_syllabic() {
+                    nucleus.push(*phone);
                 } else {
-                    coda.push(phone.clone());
+                    coda.push(*phone);
                     state = SyllablePart::Coda { layer: 1 };
                 }
             } else { // state == SyllablePart::Coda
-                if phone.is_consonant() {
-                    coda.push(phone.clone());
-                    state = match state {
-                        SyllablePart::Coda { layer } => SyllablePart::Coda { layer: layer + 1 },
-                        _ => { unreachable!() }
-                    };
-                } else {
+                if phone.is_syllabic() {
                     return None;
                 }
+                coda.push(*phone);
+                state = match state {
+                    SyllablePart::Coda { layer } => SyllablePart::Coda { layer: layer + 1 },
+                    _ => { unreachable!() }
+                };
             }
         }
 
-        return Some((onset, vowel, coda));
+        if nucleus.is_empty() { return None };
+        Some((onset, nucleus, coda))
     }
 }
 
@@ -244,4 +174,101 @@ impl IndexMut<usize> for Syllable {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.phonemes[index]
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::*;
+
+    fn small_dictionary() -> Vec<(String, Vec<Syllable>)> {
+        let path = env::var("CARGO_MANIFEST_DIR").unwrap() + "/assets/resources/cmudict.0.6-syl_SMALL.txt";
+        let contents = fs::read_to_string(path).unwrap();
+        contents
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .map(|line| {
+                let (word, sounds) = line.split_once("  ").unwrap();
+                (word.to_owned(), sounds.split('.').map(|arpabet| Syllable::from_arpabet(arpabet).unwrap()).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn syllabic_consonants_are_nuclei() {
+        let (onset, nucleus, coda) = Syllable::from_arpabet("T EL").unwrap().split().unwrap();
+        assert_eq!(onset, vec![Phoneme::T]);
+        assert_eq!(nucleus, vec![Phoneme::EL]);
+        assert!(coda.is_empty());
+
+        let (onset, nucleus, coda) = Syllable::from_arpabet("T EN Z").unwrap().split().unwrap();
+        assert_eq!(onset, vec![Phoneme::T]);
+        assert_eq!(nucleus, vec![Phoneme::EN]);
+        assert_eq!(coda, vec![Phoneme::Z]);
+    }
+
+    #[test]
+    fn r_colored_vowels_are_nuclei() {
+        let (onset, nucleus, coda) = Syllable::from_arpabet("B ER1 D").unwrap().split().unwrap();
+        assert_eq!(onset, vec![Phoneme::B]);
+        assert_eq!(nucleus, vec![Phoneme::ER]);
+        assert_eq!(coda, vec![Phoneme::D]);
+
+        let (_, nucleus, _) = Syllable::from_arpabet("T AXR").unwrap().split().unwrap();
+        assert_eq!(nucleus, vec![Phoneme::AXR]);
+    }
+
+    #[test]
+    fn syllables_without_a_nucleus_are_invalid() {
+        assert!(Syllable::from_arpabet("S T").unwrap().split().is_none());
+        assert!(Syllable::from_arpabet("T AH EL").unwrap().split().is_some());
+        assert!(Syllable::from_arpabet("T AH N EL").unwrap().split().is_none());
+    }
+
+    #[test]
+    fn stress_is_read_from_digits() {
+        assert_eq!(Syllable::from_arpabet("K AE1 T").unwrap().stress(), Stress::Primary);
+        assert_eq!(Syllable::from_arpabet("T ER2").unwrap().stress(), Stress::Secondary);
+        assert_eq!(Syllable::from_arpabet("T AH0").unwrap().stress(), Stress::Unstressed);
+        assert_eq!(Syllable::from_arpabet("T EL").unwrap().stress(), Stress::Unstressed);
+    }
+
+    #[test]
+    fn respells_syllabic_consonants_and_r_colored_vowels() {
+        assert_eq!(Syllable::from_arpabet("T EL").unwrap().to_english(), "tul");
+        assert_eq!(Syllable::from_arpabet("B ER1 D").unwrap().to_english(), "burd");
+        assert_eq!(Syllable::from_arpabet("T AXR").unwrap().to_english(), "ter");
+    }
+
+    #[test]
+    fn every_dictionary_syllable_has_a_nucleus() {
+        let words = small_dictionary();
+        assert!(!words.is_empty());
+
+        for (word, syllables) in words.iter() {
+            for syl in syllables.iter() {
+                let (_, nucleus, _) = syl.split().unwrap_or_else(|| panic!("{} has a syllable without a nucleus: {}", word, syl.to_arpabet()));
+                assert!(nucleus.iter().all(|phone| phone.is_syllabic()));
+            }
+        }
+    }
+
+    #[test]
+    fn dictionary_r_colored_vowels_stay_in_the_nucleus() {
+        let words = small_dictionary();
+        let mut found = false;
+
+        for (_word, syllables) in words.iter() {
+            for syl in syllables.iter() {
+                let (onset, nucleus, coda) = syl.split().unwrap();
+                if syl.phonemes.contains(&Phoneme::ER) {
+                    found = true;
+                    assert!(nucleus.contains(&Phoneme::ER));
+                    assert!(!onset.contains(&Phoneme::ER) && !coda.contains(&Phoneme::ER));
+                }
+            }
+        }
+        assert!(found);
+    }
+}
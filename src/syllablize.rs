@@ -1,44 +1,78 @@
-use std::{collections::HashMap, env, fs::{self, File}, io::Write, path::Path, sync::{Arc, Mutex}};
+use std::{collections::HashMap, env, fmt, fs::{self, File}, io::{self, Write}, path::Path, sync::{Arc, Mutex}};
 use indicatif::ParallelProgressIterator;
 use rayon::iter::{ParallelBridge, ParallelIterator};
 use regex::Regex;
 use serde::{Serialize, Deserialize};
 
-use crate::{logger::{ProgressBarElements, TerminalLogger, WorkIndex, WorkMessage}, syllable::Syllable};
+use crate::{inventory::Inventory, logger::{ProgressBarElements, TerminalLogger, WorkIndex, WorkMessage}, syllable::Syllable};
+
+
+/// Why the lexicon of the inventory in use could not be trained on.
+#[derive(Debug)]
+pub enum LexiconError {
+    /// The inventory doesn't name a lexicon, as when generating from a phonology of a conlang.
+    Missing,
+    /// The lexicon or word frequency file could not be read.
+    Io { file: String, error: io::Error },
+    /// A pronunciation in the lexicon uses a symbol that is not in the inventory.
+    UnknownPhoneme { symbol: String, line: String },
+}
+
+impl fmt::Display for LexiconError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "the {} inventory has no lexicon of real words", Inventory::current().name),
+            Self::Io { file, error } => write!(f, "could not read {}: {}", file, error),
+            Self::UnknownPhoneme { symbol, line } => write!(
+                f,
+                "{} is not in the {} inventory, in the lexicon line: {}",
+                symbol,
+                Inventory::current().name,
+                line
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LexiconError {}
 
 
 #[derive(Serialize, Deserialize)]
 pub struct SyllablizedPhonemes {
     // sorted by frequency
-    pub words: Vec<(String, Vec<Syllable>)>
+    pub words: Vec<(String, Vec<Syllable>)>,
+    /// Other pronunciations of words, written `WORD(2)` in the lexicon. These are not trained on. 
+    pub alternates: Vec<(String, Vec<Syllable>)>,
+    /// How often each word appears in the word frequency list, for the words in it. 
+    /// Caches made before they were kept don't load, so they are regenerated. 
+    pub frequencies: HashMap<String, usize>,
 }
 
 
 impl SyllablizedPhonemes {
-    const CACHE_FILE: &'static str = "assets/internal/syllablized-phonemes.ron";
-    const WORD_FREQUENCY_FILE: &'static str = "assets/resources/word_frequency.txt";
-    const CMU_FILE: &'static str = "assets/resources/cmudict.0.6-syl.txt";
+    const CACHE_FILE: &'static str = "syllablized-phonemes.ron";
 
-    pub fn new(logger: &mut TerminalLogger) -> Self {
-        let mut syl_phones = Self { words: Vec::new() };
+    pub fn new(logger: &mut TerminalLogger) -> Result<Self, LexiconError> {
+        let mut syl_phones = Self { words: Vec::new(), alternates: Vec::new(), frequencies: HashMap::new() };
 
-        if let Ok(contents) = Self::try_read_cache() {
-            syl_phones.load(contents);
-        } else {
-            syl_phones.generate(logger);
+        let loaded = Self::try_read_cache().ok().and_then(|contents| syl_phones.load(contents));
+        if loaded.is_none() {
+            syl_phones.generate(logger)?;
         }
 
-        syl_phones
+        Ok(syl_phones)
     }
 
     fn cache_file() -> String {
-        env::current_dir().unwrap().to_str().unwrap().to_owned() + "/" + Self::CACHE_FILE
+        Inventory::current().cache_file(Self::CACHE_FILE)
     }
-    fn word_freq_file() -> String {
-        env::current_dir().unwrap().to_str().unwrap().to_owned() + "/" + Self::WORD_FREQUENCY_FILE
+    fn word_freq_file() -> Option<String> {
+        let file = Inventory::current().word_frequency.as_ref()?;
+        Some(env::current_dir().unwrap().to_str().unwrap().to_owned() + "/" + file)
     }
-    fn cmu_file() -> String {
-        env::current_dir().unwrap().to_str().unwrap().to_owned() + "/" + Self::CMU_FILE
+    fn lexicon_file() -> Result<String, LexiconError> {
+        let file = Inventory::current().lexicon.as_ref().ok_or(LexiconError::Missing)?;
+        Ok(env::current_dir().unwrap().to_str().unwrap().to_owned() + "/" + file)
     }
     pub fn cache_exists() -> bool {
         Path::new(&Self::cache_file()).exists()
@@ -56,55 +90,76 @@ impl SyllablizedPhonemes {
         Some(())
     }
 
-    fn load_word_frequencies() -> Vec<String> {
-        let contents = fs::read_to_string(Self::word_freq_file())
-            .expect("Failed to load word frequency file");
+    fn read(file: String) -> Result<String, LexiconError> {
+        fs::read_to_string(&file).map_err(|error| LexiconError::Io { file, error })
+    }
+
+    fn load_word_frequencies() -> Result<Vec<(String, usize)>, LexiconError> {
+        let Some(file) = Self::word_freq_file() else { return Ok(vec![]) };
+        let contents = Self::read(file)?;
         let lines = contents.split("\n");
-        let words: Vec<String> = lines
-            .map(|line| line.split("\t").nth(0).unwrap().to_owned())
+        let mut words: Vec<(String, usize)> = lines
+            .map(|line| {
+                let mut columns = line.split("\t");
+                let word = columns.next().unwrap().to_owned();
+                (word, columns.next().and_then(|count| count.trim().parse().ok()).unwrap_or(0))
+            })
             .collect();
 
         // only consider first 60,000 most frequent words
-        words.split_at(60000).0.to_vec()
+        words.truncate(60000);
+        Ok(words)
     }
 
-    pub fn generate(&mut self, logger: &mut TerminalLogger) {
+    /// Syllablizes the lexicon of the inventory in use and caches the result. 
+    /// 
+    /// Fails without writing the cache if a file can't be read or a pronunciation uses a 
+    /// symbol that is not in the inventory. 
+    pub fn generate(&mut self, logger: &mut TerminalLogger) -> Result<(), LexiconError> {
         let read_work_freqs_work = logger.begin_work(WorkMessage::new("Reading", "Word Frequencies", WorkIndex::new(1, 5)));
 
-        let word_freqs = Self::load_word_frequencies();
+        let word_freqs = Self::load_word_frequencies()?;
         let word_syllables_mutex: Arc<Mutex<HashMap<String, Vec<Syllable>>>> = Arc::new(Mutex::new(HashMap::new()));
+        let alternates_mutex = Arc::new(Mutex::new(Vec::<(String, usize, Vec<Syllable>)>::new()));
+        let alternate_regex = Regex::new(r"^(.*)\((\d+)\)$").unwrap();
 
         logger.sleep(0.25);
         logger.finish_work(read_work_freqs_work);
-        let read_cmu_work = logger.begin_work(WorkMessage::new("Reading", "CMU Dictionary", WorkIndex::new(2, 5)));
+        let read_cmu_work = logger.begin_work(WorkMessage::new("Reading", "Lexicon", WorkIndex::new(2, 5)));
 
-        let cmu_file = fs::read_to_string(Self::cmu_file())
-            .expect("Failed to load cmu file");
+        let cmu_file = Self::read(Self::lexicon_file()?)?;
         let lines = cmu_file.lines();
 
         logger.sleep(0.25);
         logger.finish_work(read_cmu_work);
-        let parse_cmu_work = logger.begin_work(WorkMessage::new("Parsing", "CMU Dictionary", WorkIndex::new(3, 5)));
+        let parse_cmu_work = logger.begin_work(WorkMessage::new("Parsing", "Lexicon", WorkIndex::new(3, 5)));
 
         let bar = logger.create_progress(cmu_file.lines().count() as u64, ProgressBarElements::PERCENTAGE | ProgressBarElements::ETA);
 
-        lines.par_bridge().progress_with(bar).for_each(|line| {
-            if line.starts_with("#") { return };
-            if line.trim() == "" { return };
+        lines.par_bridge().progress_with(bar).try_for_each(|line| {
+            if line.starts_with("#") { return Ok(()) };
+            if line.trim() == "" { return Ok(()) };
 
             let elements: Vec<&str> = line.splitn(2, "  ").collect();
             let word = elements[0];
             let sounds = elements[1];
 
-            if Regex::new(r".*\(\d+\)$").unwrap().is_match(word) { return };
             let syllables: Vec<Syllable> = sounds
                 .split(".")
-                .map(|arpabet| Syllable::from_arpabet(arpabet))
-                .collect();
+                .map(Syllable::from_arpabet)
+                .collect::<Result<_, _>>()
+                .map_err(|symbol| LexiconError::UnknownPhoneme { symbol, line: line.to_owned() })?;
+
+            if let Some(captures) = alternate_regex.captures(word) {
+                let variant = captures[2].parse().unwrap_or(0);
+                alternates_mutex.lock().unwrap().push((captures[1].to_lowercase(), variant, syllables));
+                return Ok(());
+            }
             
             let word_lower = word.to_lowercase();
             word_syllables_mutex.lock().unwrap().insert(word_lower, syllables);
-        });
+            Ok(())
+        })?;
         
         let mut word_syllables = word_syllables_mutex.lock().unwrap();
 
@@ -112,10 +167,12 @@ impl SyllablizedPhonemes {
         let ordering_work = logger.begin_work(WorkMessage::new("Ordering", "Words By Frequency", WorkIndex::new(4, 5)));
 
         let mut ordered_words = vec![];
-        for word in word_freqs {
+        let mut frequencies = HashMap::new();
+        for (word, frequency) in word_freqs {
             if let Some(syls) = word_syllables.get(&word) {
                 ordered_words.push((word.clone(), syls.clone()));
                 word_syllables.remove(&word);
+                frequencies.insert(word, frequency);
             }
         }
 
@@ -128,12 +185,18 @@ impl SyllablizedPhonemes {
         logger.finish_work(ordering_work);
         let writing_work = logger.begin_work(WorkMessage::new("Writing", "Syllablized Phonemes to File", WorkIndex::new(5, 5)));
 
+        let mut alternates = alternates_mutex.lock().unwrap();
+        alternates.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+
         self.words = ordered_words;
+        self.frequencies = frequencies;
+        self.alternates = alternates.drain(..).map(|(word, _, syllables)| (word, syllables)).collect();
         let mut file = File::create(Self::cache_file()).unwrap();
         file.write_all(ron::ser::to_string_pretty(&self, ron::ser::PrettyConfig::default()).unwrap().as_bytes())
             .expect("Failed to write to file");
 
         logger.sleep(0.25);
         logger.finish_work(writing_work);
+        Ok(())
     }
 }
\ No newline at end of file
@@ -1,6 +1,9 @@
 use rand::{rngs::ThreadRng, Rng};
 
-pub fn weighted_random_choice<T: Clone>(a: &Vec<(usize, T)>, rng: &mut ThreadRng) -> T {
+/// Picks an element with probability proportional to its weight. 
+/// 
+/// Returns none if there are no elements or every weight is zero. 
+pub fn weighted_random_choice<T: Clone>(a: &Vec<(usize, T)>, rng: &mut ThreadRng) -> Option<T> {
     let mut weights = Vec::new();
 
     for i in 0..a.len() {
@@ -11,7 +14,10 @@ pub fn weighted_random_choice<T: Clone>(a: &Vec<(usize, T)>, rng: &mut ThreadRng
         }
     }
 
-    let rand = rng.gen_range(0..weights[weights.len() - 1]);
+    let total = *weights.last()?;
+    if total == 0 { return None };
+
+    let rand = rng.gen_range(0..total);
     let mut i = 0;
     for _ in 0..weights.len() {
         if weights[i] > rand {
@@ -19,10 +25,11 @@ pub fn weighted_random_choice<T: Clone>(a: &Vec<(usize, T)>, rng: &mut ThreadRng
         }
         i += 1;
     }
-    return a[i].1.clone();
+    return Some(a[i].1.clone());
 }
 
-pub fn random_choice<T: Clone>(a: &Vec<(usize, T)>, rng: &mut ThreadRng) -> T {
+pub fn random_choice<T: Clone>(a: &Vec<(usize, T)>, rng: &mut ThreadRng) -> Option<T> {
+    if a.is_empty() { return None };
     let rand = rng.gen_range(0..a.len());
-    return a[rand].1.clone();
+    return Some(a[rand].1.clone());
 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+use core::fmt;
+use std::collections::HashSet;
+
+use crate::{connections::SyllableConnections, graph::{NodeData, NodeID, SonorityGraph}, phoneme::{Phoneme, SyllablePart}};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Defect {
+    /// There is no way to start a syllable or a word.
+    MissingStart,
+    /// An edge points at a node that is not in the graph.
+    DanglingEdge { from: NodeID, to: NodeID },
+    /// An edge finishes a syllable before it has a nucleus.
+    SkipsNucleus { from: NodeID, to: NodeID },
+    /// A reachable node has no edges to continue with.
+    DeadEnd(NodeID),
+    /// A reachable node has edges, but none of them eventually lead to a stop.
+    NoPathToStop(NodeID),
+    /// A connection starts a syllable with a phoneme the graph has no node for.
+    MissingSyllableStart { from: NodeData, to: Phoneme },
+    /// A phoneme can end a syllable, but the connections do not say what comes after it.
+    MissingConnection(Phoneme),
+}
+
+impl Defect {
+    pub fn is_repairable(&self) -> bool {
+        !matches!(self, Self::MissingStart)
+    }
+}
+
+impl fmt::Display for Defect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingStart => write!(f, "no way to start a syllable or word"),
+            Self::DanglingEdge { from, to } => write!(f, "edge {:?} -> {:?} points at a missing node", from, to),
+            Self::SkipsNucleus { from, to } => write!(f, "edge {:?} -> {:?} finishes a syllable without a nucleus", from, to),
+            Self::DeadEnd(id) => write!(f, "node {:?} has no outgoing edges", id),
+            Self::NoPathToStop(id) => write!(f, "node {:?} has no path to a stop", id),
+            Self::MissingSyllableStart { from, to } => write!(f, "connection {:?} -> {:?} has no graph node to start from", from, to),
+            Self::MissingConnection(phoneme) => write!(f, "nothing is known to follow a syllable ending in {:?}", phoneme),
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    /// Defects that were fixed.
+    pub repaired: Vec<Defect>,
+    /// Defects that are still present.
+    pub remaining: Vec<Defect>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.remaining.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Repaired {} defects, {} remaining", self.repaired.len(), self.remaining.len())?;
+        for defect in self.repaired.iter() {
+            writeln!(f, "  repaired: {}", defect)?;
+        }
+        for defect in self.remaining.iter() {
+            writeln!(f, "  remaining: {}", defect)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Checks that every syllable and word the model can start can also be finished.
+pub fn validate(graph: &SonorityGraph, connections: &SyllableConnections) -> ValidationReport {
+    let mut defects = Vec::new();
+
+    let start_id = NodeID { data: NodeData::Start, part: SyllablePart::Onset };
+    let has_graph_start = graph.get_node(start_id).is_some_and(|node| node.outs.iter().any(|edge| edge.count > 0));
+    let has_word_start = connections.connections
+        .get(&NodeData::Start)
+        .is_some_and(|edges| edges.iter().any(|edge| edge.0 > 0));
+    if !has_graph_start || !has_word_start {
+        defects.push(Defect::MissingStart);
+    }
+
+    let mut roots = vec![start_id];
+    for (from, edges) in connections.connections.iter() {
+        for (_count, to) in edges.iter() {
+            let NodeData::Phoneme(phoneme) = to else { continue };
+            let id = SonorityGraph::start_id(*phoneme);
+            match graph.get_node(id) {
+                Some(_) => roots.push(id),
+                None => defects.push(Defect::MissingSyllableStart { from: *from, to: *phoneme }),
+            }
+        }
+    }
+
+    let reachable = graph.reachable_from(&roots);
+    let reaching_stop = graph.nodes_reaching_stop();
+    let mut missing_connections = HashSet::new();
+
+    for id in reachable.iter() {
+        if id.data == NodeData::Stop { continue };
+        let node = graph.get_node_unchecked(*id);
+
+        for edge in node.outs.iter() {
+            match graph.get_node(edge.to) {
+                None => defects.push(Defect::DanglingEdge { from: *id, to: edge.to }),
+                Some(_) if edge.to.data == NodeData::Stop && id.part == SyllablePart::Onset => {
+                    defects.push(Defect::SkipsNucleus { from: *id, to: edge.to });
+                },
+                Some(_) if edge.to.data == NodeData::Stop => {
+                    if let NodeData::Phoneme(phoneme) = id.data {
+                        missing_connections.insert(phoneme);
+                    }
+                },
+                Some(_) => (),
+            }
+        }
+
+        if !node.outs.iter().any(|edge| edge.count > 0) {
+            defects.push(Defect::DeadEnd(*id));
+        } else if !reaching_stop.contains(id) {
+            defects.push(Defect::NoPathToStop(*id));
+        }
+    }
+
+    for phoneme in missing_connections {
+        let has_connection = connections.connections
+            .get(&NodeData::Phoneme(phoneme))
+            .is_some_and(|edges| edges.iter().any(|edge| edge.0 > 0));
+        if !has_connection {
+            defects.push(Defect::MissingConnection(phoneme));
+        }
+    }
+
+    ValidationReport { repaired: Vec::new(), remaining: defects }
+}
+
+/// Repeatedly fixes the defects found by [`validate`] until none are left that can be repaired.
+///
+/// Broken nodes are removed along with every edge pointing at them, connections to missing
+/// syllable starts are removed, and phonemes with no known continuation end the word.
+pub fn repair(graph: &mut SonorityGraph, connections: &mut SyllableConnections) -> ValidationReport {
+    let mut repaired = Vec::new();
+
+    loop {
+        let report = validate(graph, connections);
+        let fixable: Vec<Defect> = report.remaining.into_iter().filter(|defect| defect.is_repairable()).collect();
+        if fixable.is_empty() { break };
+
+        for defect in fixable.iter() {
+            match *defect {
+                Defect::MissingStart => unreachable!(),
+                Defect::DanglingEdge { from, to } | Defect::SkipsNucleus { from, to } => {
+                    if let Some(node) = graph.get_node_mut(from) {
+                        node.outs.retain(|edge| edge.to != to);
+                    }
+                },
+                Defect::DeadEnd(id) | Defect::NoPathToStop(id) => {
+                    graph.nodes.remove(&id);
+                    for node in graph.nodes.values_mut() {
+                        node.outs.retain(|edge| edge.to != id);
+                    }
+                },
+                Defect::MissingSyllableStart { from, to } => {
+                    if let Some(edges) = connections.connections.get_mut(&from) {
+                        edges.retain(|edge| edge.1 != NodeData::Phoneme(to));
+                    }
+                },
+                Defect::MissingConnection(phoneme) => {
+                    connections.connections.insert(NodeData::Phoneme(phoneme), vec![(1, NodeData::Stop)]);
+                },
+            }
+        }
+        repaired.extend(fixable);
+    }
+
+    ValidationReport { repaired, remaining: validate(graph, connections).remaining }
+}
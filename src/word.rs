@@ -1,6 +1,6 @@
 use core::fmt;
 
-use crate::syllable::Syllable;
+use crate::{inflection::Inflection, inventory::Inventory, narrow::{self, NarrowConfig}, notation::{self, Notation}, phoneme::Phoneme, sonority, syllable::{Stress, Syllable}};
 
 #[derive(Clone)]
 pub struct Word {
@@ -15,31 +15,162 @@ impl Word {
         Word { syllables: vec![] }
     }
     
+    /// Splits phonemes into syllables around each nucleus, giving each syllable the longest 
+    /// onset that doesn't break the Sonority Sequencing Principle. Each syllabic phoneme is a 
+    /// nucleus of its own unless the inventory declares the sequence it is in as a diphthong, 
+    /// so idea has three syllables. 
+    /// 
+    /// Phonemes before the first nucleus join the first syllable, and phonemes after the last 
+    /// nucleus join the last one. No phonemes make a word with no syllables. 
+    pub fn syllabify(phonemes: &[Phoneme]) -> Word {
+        Word::syllabify_at(phonemes, 0)
+    }
+
+    /// Syllabifies like [`Word::syllabify`], except that no nucleus spans `boundary`, where an 
+    /// affix was joined to a stem. 
+    pub fn syllabify_at(phonemes: &[Phoneme], boundary: usize) -> Word {
+        if phonemes.is_empty() {
+            return Word::empty();
+        }
+
+        // the start and end of each nucleus
+        let inventory = Inventory::current();
+        let mut nuclei: Vec<(usize, usize)> = Vec::new();
+        for i in (0..phonemes.len()).filter(|i| phonemes[*i].is_syllabic()) {
+            match nuclei.last_mut() {
+                Some((start, end)) if *end == i && i != boundary && starts_diphthong(inventory, &phonemes[*start..=i]) => *end += 1,
+                _ => nuclei.push((i, i + 1)),
+            }
+        }
+        if nuclei.is_empty() {
+            return Word::new(vec![Syllable::from_phonemes(phonemes.to_vec())]);
+        }
+
+        // where each syllable after the first begins
+        let mut starts = vec![0];
+        for pair in nuclei.windows(2) {
+            let cluster = &phonemes[pair[0].1..pair[1].0];
+            let onset_start = (0..=cluster.len())
+                .find(|start| sonority::onset_is_valid(&cluster[*start..]))
+                .unwrap_or(cluster.len());
+            starts.push(pair[0].1 + onset_start);
+        }
+        starts.push(phonemes.len());
+
+        Word::new(starts.windows(2).map(|range| Syllable::from_phonemes(phonemes[range[0]..range[1]].to_vec())).collect())
+    }
+
+    /// Reads an X-SAMPA transcription such as `"kIt.S@n`, syllabifying it again. Stress marks,
+    /// length marks and syllable breaks are ignored.
+    ///
+    /// Returns none if part of the transcription isn't a phoneme of the inventory in use.
+    pub fn from_xsampa(xsampa: &str) -> Option<Word> {
+        Word::from_ipa(&Notation::XSampa.read(xsampa))
+    }
+
+    /// Reads an IPA transcription such as `ˈkɪ.tʃən`, syllabifying it again. Stress marks,
+    /// length marks and syllable breaks are ignored.
+    ///
+    /// Returns none if part of the transcription isn't a phoneme of the inventory in use.
+    pub fn from_ipa(ipa: &str) -> Option<Word> {
+        let phonemes = notation::ipa_to_phonemes(ipa)?;
+        if phonemes.is_empty() { return None };
+        Some(Word::syllabify(&phonemes))
+    }
+
+    /// Syllabifies `phonemes` like [`Word::syllabify_at`], giving primary stress to the syllable
+    /// with the phoneme at `stressed`.
+    pub fn resyllabify(phonemes: &[Phoneme], boundary: usize, stressed: usize) -> Word {
+        let mut word = Word::syllabify_at(phonemes, boundary);
+        let mut end = 0;
+        let syllable = word.syllables.iter().position(|syl| {
+            end += syl.phonemes().len();
+            end > stressed
+        });
+        word.stress_syllable(syllable.unwrap_or(0));
+        word
+    }
+
+    /// Gives primary stress to the syllable at `index`, and none to the others.
+    pub fn stress_syllable(&mut self, index: usize) {
+        for (i, syl) in self.syllables.iter_mut().enumerate() {
+            syl.set_stress(if i == index { Stress::Primary } else { Stress::Unstressed });
+        }
+    }
+
+    /// Returns the index of the syllable with primary stress, guessed like
+    /// [`narrow::stresses`] does for generated words.
+    pub fn stressed_syllable(&self) -> usize {
+        narrow::stresses(self).iter().position(|stress| *stress == Stress::Primary).unwrap_or(0)
+    }
+
+    /// Returns the index of the first phoneme of the syllable with primary stress.
+    pub fn stressed_phoneme(&self) -> usize {
+        self.syllables[..self.stressed_syllable()].iter().map(|syl| syl.phonemes().len()).sum()
+    }
+
+    /// Returns the plural, possessive, past, third person or -ing form of the word, with the
+    /// ending that suits its last phoneme, syllabified again.
+    pub fn inflect(&self, inflection: Inflection) -> Word {
+        inflection.apply(self)
+    }
+
     pub fn add_syllable(&mut self, syllable: Syllable) {
         self.syllables.push(syllable);
     }
 
+    /// Returns the phonemes of every syllable in order. 
+    pub fn phonemes(&self) -> Vec<Phoneme> {
+        self.syllables.iter().flat_map(|syl| syl.phonemes().iter().copied()).collect()
+    }
+
     pub fn to_english(&self) -> String {
-        let mut res = String::from(&self.syllables.first().unwrap().to_english());
-        for syl in &self.syllables[1..] {
-            res += "-";
-            res += &syl.to_english();
-        };
-        res
+        self.syllables.iter().map(|syl| syl.to_english()).collect::<Vec<String>>().join("-")
     }
 
     pub fn to_ipa(&self) -> String {
-        let mut res = String::from(&self.syllables.first().unwrap().to_ipa());
-        for syl in &self.syllables[1..] {
-            res += " ";
-            res += &syl.to_ipa();
-        };
-        res
+        self.syllables.iter().map(|syl| syl.to_ipa()).collect::<Vec<String>>().join(" ")
     }
+
+    pub fn to_notation(&self, notation: Notation) -> String {
+        notation.transcribe(&self.syllables.iter().map(|syl| syl.to_ipa()).collect::<Vec<String>>())
+    }
+
+    /// Returns a narrow transcription with English allophony, stress marks and periods between syllables. 
+    pub fn to_narrow_ipa(&self) -> String {
+        narrow::transcribe(self, &NarrowConfig::default())
+    }
+}
+
+fn starts_diphthong(inventory: &Inventory, phonemes: &[Phoneme]) -> bool {
+    inventory.starts_diphthong(&phonemes.iter().map(|phoneme| phoneme.to_arpabet()).collect::<Vec<&str>>())
 }
 
 impl fmt::Display for Word {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{} ({})", self.to_english(), self.to_ipa())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syllabify(arpabet: &str) -> Vec<String> {
+        let phonemes: Vec<Phoneme> = arpabet.split_whitespace().map(|symbol| Phoneme::try_from_arpabet(symbol).unwrap()).collect();
+        Word::syllabify(&phonemes).syllables.iter().map(|syl| syl.to_arpabet().trim_end().to_owned()).collect()
+    }
+
+    #[test]
+    fn vowels_in_hiatus_are_separate_syllables() {
+        assert_eq!(syllabify("AY D IY AH"), ["AY", "D IY", "AH"]);
+        assert_eq!(syllabify("K R IY EY T"), ["K R IY", "EY T"]);
+        assert_eq!(syllabify("P IY AE N OW"), ["P IY", "AE", "N OW"]);
+    }
+
+    #[test]
+    fn onsets_are_as_long_as_sonority_allows() {
+        assert_eq!(syllabify("EH K S T R AH"), ["EH K", "S T R AH"]);
+        assert_eq!(syllabify("K AE T"), ["K AE T"]);
+        assert!(syllabify("").is_empty());
+    }
+}
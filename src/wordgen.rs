@@ -2,7 +2,11 @@ use std::collections::HashMap;
 
 use rand::{rngs::ThreadRng, thread_rng, Rng};
 
-use crate::{connections::SyllableConnections, graph::{NodeData, SonorityGraph}, logger::{TerminalLogger, WorkIndex, WorkMessage}, syllablize::SyllablizedPhonemes, word::Word};
+use crate::{batch::BatchConstraints, connections::SyllableConnections, filter::{InventoryError, InventoryFilter}, graph::{NodeData, NodeID, SonorityGraph}, lexicon::Lexicon, logger::{TerminalLogger, WorkID, WorkIndex, WorkMessage}, metrics::{PositionalProbabilities, WordMetrics}, neighbors::BkTree, phoneme::Phoneme, phonology::{Phonology, PhonologyError, SyllableSet}, prune::{PruneConfig, PruneReport}, rhyme::Rhyme, sonority, syllable::Syllable, syllablize::{LexiconError, SyllablizedPhonemes}, validate::{self, ValidationReport}, word::Word};
+
+
+/// Decides whether a syllable being generated can move on to a node. 
+type SyllableFilter<'a> = &'a dyn Fn(&Syllable, NodeID) -> bool;
 
 
 pub struct WordGenConfig {
@@ -13,6 +17,18 @@ pub struct WordGenConfig {
     pub word_length_bias: f32,
     /// Determines the maximum number of syllables a word can have. 
     pub word_length_max: usize,
+    /// If set, rare edges are pruned from the model after it is loaded. 
+    pub prune: Option<PruneConfig>,
+    /// Forbids onsets and codas that break the Sonority Sequencing Principle. 
+    pub enforce_sonority: bool,
+    /// Phonemes that are never generated, and phonemes generated in place of others. 
+    pub inventory: InventoryFilter,
+    /// Words with a lower [`BkTree::novelty`] than this are generated again. Novelty is counted in 
+    /// inserted or deleted phonemes, and substituting a similar phoneme costs less than one, so 0 
+    /// allows real words and 1 rejects pat when bat is real. 
+    pub min_distance: f32,
+    /// Sounds every word generated shares. 
+    pub batch: BatchConstraints,
 }
 
 impl Default for WordGenConfig {
@@ -21,6 +37,11 @@ impl Default for WordGenConfig {
             word_length_decay: 1.5,
             word_length_bias: 1.5,
             word_length_max: 10,
+            prune: None,
+            enforce_sonority: false,
+            inventory: InventoryFilter::new(),
+            min_distance: 0.0,
+            batch: BatchConstraints::default(),
         }
     }
 }
@@ -32,16 +53,29 @@ pub struct FakeWordGenerator {
     pub syllable_connections: SyllableConnections,
     pub rng: ThreadRng,
     pub config: WordGenConfig,
+    /// What was removed by pruning, if `config.prune` was set. 
+    pub prune_report: Option<PruneReport>,
+    /// Defects found in the model when it was loaded, and which of them were repaired. 
+    pub validation_report: ValidationReport,
+    /// The only syllables that can be generated, when the model was compiled from a phonology. 
+    pub allowed_syllables: Option<SyllableSet>,
+    /// The training words indexed by how they sound, once loaded. 
+    pub real_words: Option<BkTree>,
+    /// How likely segments are at each position of the training words, loaded with `real_words`. 
+    pub positional_probabilities: Option<PositionalProbabilities>,
 }
 
 impl FakeWordGenerator {
-    pub fn new(config: WordGenConfig) -> Self {
+    /// Loads the model, building whatever is not cached, and applies the restrictions in `config`. 
+    /// 
+    /// Returns an error if the phoneme inventory is restricted so much that no words can be generated. 
+    pub fn new(config: WordGenConfig) -> Result<Self, InventoryError> {
         let mut logger = TerminalLogger::new();
 
         logger.initialize();
         let init_work = logger.begin_work(WorkMessage::new("Initializing", "Fake Word Generator", WorkIndex::None));
 
-        let mut syllablized_phonemes = SyllablizedPhonemes { words: vec![] };
+        let mut syllablized_phonemes = SyllablizedPhonemes { words: vec![], alternates: vec![], frequencies: HashMap::new() };
         let mut sonority_graph = SonorityGraph { nodes: HashMap::new() };
         let mut syllable_connections = SyllableConnections { connections: HashMap::new() };
 
@@ -89,7 +123,7 @@ impl FakeWordGenerator {
 
             if regenerate {
                 logger.begin_section();
-                syllablized_phonemes.generate(&mut logger);
+                syllablized_phonemes.generate(&mut logger)?;
                 logger.end_section();
             }
             logger.finish_work(gen_syl_phones_work);
@@ -194,24 +228,274 @@ impl FakeWordGenerator {
         logger.finish_work(gen_conn_work);
         logger.end_section();
 
+        let mut generator = Self::prepare(logger, init_work, syllablized_phonemes, sonority_graph, syllable_connections, config)?;
+        if generator.config.min_distance > 0.0 {
+            generator.load_real_words()?;
+        }
+        Ok(generator)
+    }
+
+    /// Builds the model from a phonology instead of a lexicon, then applies the restrictions in `config`. 
+    /// 
+    /// Nothing is cached, since compiling a phonology is fast. 
+    pub fn from_phonology(phonology: &Phonology, config: WordGenConfig) -> Result<Self, PhonologyError> {
+        let mut logger = TerminalLogger::new();
+
+        logger.initialize();
+        let init_work = logger.begin_work(WorkMessage::new("Initializing", "Fake Word Generator", WorkIndex::None));
+        logger.begin_section();
+
+        let compile_work = logger.begin_work(WorkMessage::new("Compiling", "Phonology", WorkIndex::None));
+        let (sonority_graph, syllable_connections) = match phonology.compile() {
+            Ok(model) => model,
+            Err(err) => {
+                logger.fail_work(compile_work, "Invalid Phonology");
+                logger.end_section();
+                logger.finish();
+                return Err(err);
+            }
+        };
+        logger.finish_work(compile_work);
+
+        // the set has to match the graph once the inventory filter is applied to it
+        let syllables: Vec<(Syllable, usize)> = phonology.syllables()?
+            .into_iter()
+            .filter_map(|(syllable, weight)| Some((config.inventory.map_syllable(&syllable)?, weight)))
+            .collect();
+        let allowed_syllables = SyllableSet::new(&syllables);
+
+        let syllablized_phonemes = SyllablizedPhonemes { words: vec![], alternates: vec![], frequencies: HashMap::new() };
+        let mut generator = Self::prepare(logger, init_work, syllablized_phonemes, sonority_graph, syllable_connections, config)
+            .map_err(PhonologyError::Restriction)?;
+        generator.allowed_syllables = Some(allowed_syllables);
+        if generator.config.min_distance > 0.0 {
+            generator.load_real_words().map_err(|err| PhonologyError::Restriction(err.into()))?;
+        }
+        Ok(generator)
+    }
+
+    /// Repairs the model and applies the restrictions in `config`, finishing the work begun by the constructors. 
+    fn prepare(
+        mut logger: TerminalLogger,
+        init_work: WorkID,
+        syllablized_phonemes: SyllablizedPhonemes,
+        mut sonority_graph: SonorityGraph,
+        mut syllable_connections: SyllableConnections,
+        config: WordGenConfig,
+    ) -> Result<Self, InventoryError> {
+        let validate_work = logger.begin_work(WorkMessage::new("Validating", "Model", WorkIndex::None));
+        let mut validation_report = validate::repair(&mut sonority_graph, &mut syllable_connections);
+        if validation_report.is_valid() {
+            logger.finish_work(validate_work);
+        } else {
+            logger.fail_work(validate_work, "Model Has Unrepairable Defects");
+        }
+
+        if !config.inventory.is_empty() {
+            let restrict_work = logger.begin_work(WorkMessage::new("Restricting", "Phoneme Inventory", WorkIndex::None));
+            match config.inventory.apply(&mut sonority_graph, &mut syllable_connections) {
+                Ok(report) => {
+                    validation_report.repaired.extend(report.repaired);
+                    logger.finish_work(restrict_work);
+                },
+                Err(err) => {
+                    logger.fail_work(restrict_work, "Generation Impossible");
+                    logger.end_section();
+                    logger.finish();
+                    return Err(err);
+                }
+            }
+        }
+
+        let mut prune_report = None;
+        if let Some(prune_config) = &config.prune {
+            let prune_work = logger.begin_work(WorkMessage::new("Pruning", "Rare Edges", WorkIndex::None));
+            let mut report = sonority_graph.prune(prune_config);
+            report.append(syllable_connections.prune(prune_config));
+            prune_report = Some(report);
+            logger.finish_work(prune_work);
+        }
+
+        if config.batch.is_active() {
+            // a first syllable sharing what the batch asks for has to exist after some start
+            let mut rng = thread_rng();
+            let allow = |syl: &Syllable, next: NodeID| config.batch.allows_next(syl, next);
+            let batch_can_start = syllable_connections.connections.get(&NodeData::Start).is_some_and(|starts| {
+                starts.iter().any(|(_, next)| match next {
+                    NodeData::Phoneme(phoneme) => sonority_graph.evaluate_from_start_with(*phoneme, &mut rng, &allow).is_some(),
+                    _ => false,
+                })
+            });
+            if !batch_can_start {
+                logger.end_section();
+                logger.finish();
+                return Err(InventoryError::BatchImpossible);
+            }
+        }
+
         logger.end_section();
         logger.finish_work(init_work);
         logger.finish();
 
-        Self {
+        Ok(Self {
             syllablized_phonemes,
             sonority_graph,
             syllable_connections,
             rng: thread_rng(),
             config,
+            prune_report,
+            validation_report,
+            allowed_syllables: None,
+            real_words: None,
+            positional_probabilities: None,
+        })
+    }
+
+    /// Loads the training words if they were not needed to build the model. 
+    pub fn load_syllablized_phonemes(&mut self) -> Result<(), LexiconError> {
+        if !self.syllablized_phonemes.words.is_empty() { return Ok(()) };
+
+        let mut logger = TerminalLogger::new();
+        logger.initialize();
+        let load_work = logger.begin_work(WorkMessage::new("Loading", "Syllablized Phonemes", WorkIndex::None));
+        logger.begin_section();
+        let loaded = SyllablizedPhonemes::new(&mut logger);
+        logger.end_section();
+        match loaded {
+            Ok(syllablized_phonemes) => {
+                self.syllablized_phonemes = syllablized_phonemes;
+                logger.finish_work(load_work);
+                logger.finish();
+                Ok(())
+            },
+            Err(error) => {
+                logger.fail_work(load_work, "Failed");
+                logger.finish();
+                Err(error)
+            },
+        }
+    }
+
+    /// Indexes the training words to compare generated words against, loading them if needed. 
+    pub fn load_real_words(&mut self) -> Result<(), LexiconError> {
+        if self.real_words.is_some() { return Ok(()) };
+        self.load_syllablized_phonemes()?;
+
+        let mut logger = TerminalLogger::new();
+        logger.initialize();
+        let index_work = logger.begin_work(WorkMessage::new("Indexing", "Real Words", WorkIndex::None));
+        let lexicon = Lexicon::new(&self.syllablized_phonemes);
+        self.positional_probabilities = Some(PositionalProbabilities::new(&lexicon));
+        self.real_words = Some(BkTree::new(lexicon));
+        logger.finish_work(index_work);
+        logger.finish();
+        Ok(())
+    }
+
+    /// Measures `word` against the training words. 
+    /// 
+    /// Returns none if [`FakeWordGenerator::load_real_words`] wasn't called. 
+    pub fn measure(&self, word: &Word) -> Option<WordMetrics> {
+        Some(WordMetrics::measure(word, self.real_words.as_ref()?, self.positional_probabilities.as_ref()?))
+    }
+
+    /// Scores how plausible the model finds `word`: the average log probability of the choices 
+    /// made to generate it. Scores are never above 0, and higher scores are more plausible. 
+    /// 
+    /// Returns none if the model cannot generate `word`. 
+    pub fn score(&self, word: &Word) -> Option<f64> {
+        let mut log_probability = 0.0;
+        let mut choices = 0;
+        let mut prev = NodeData::Start;
+        for syllable in word.syllables.iter() {
+            log_probability += self.syllable_connections.probability(prev, NodeData::Phoneme(syllable.first_phoneme()))?.ln();
+            let (syllable_log_probability, syllable_choices) = self.sonority_graph.log_probability(syllable)?;
+            log_probability += syllable_log_probability;
+            choices += syllable_choices + 1;
+            prev = NodeData::Phoneme(syllable.last_phoneme());
+        }
+        // single syllable words are not in the connections, so only multi-syllable words end in them
+        if word.syllables.len() > 1 {
+            log_probability += self.syllable_connections.probability(prev, NodeData::Stop)?.ln();
+            choices += 1;
+        }
+
+        match choices {
+            0 => None,
+            _ => Some(log_probability / choices as f64),
         }
     }
 
     fn get_new_syllable_chance(&self, i: usize) -> f32 {
         self.config.word_length_decay.powf(-(i as f32)) * self.config.word_length_bias
     }
-    pub fn generate_word(&mut self) -> Word {
-        let mut cur_phone = self.syllable_connections.evaluate(NodeData::Start, &mut self.rng);
+    /// Generates a word, trying again while it is closer to a real word than `config.min_distance` 
+    /// or doesn't share what `config.batch` asks for. The first word of a batch decides what 
+    /// the rest share, and words of a vowel theme are stressed on the syllable with the shared 
+    /// nucleus. 
+    /// 
+    /// Returns none if every attempt is rejected or fails to get past the first syllable. 
+    pub fn generate_word(&mut self) -> Option<Word> {
+        for _ in 0..Self::MAX_ATTEMPTS {
+            let mut word = self.generate_candidate();
+            if word.syllables.is_empty() || self.is_too_close(&word) || !self.config.batch.allows_word(&word) { continue };
+            if self.config.batch.vowel_theme {
+                word.stress_syllable(BatchConstraints::themed_syllable(&word));
+            }
+            if !self.config.batch.is_settled() {
+                self.config.batch.settle(&word);
+            }
+            return Some(word);
+        }
+        None
+    }
+
+    fn is_too_close(&self, word: &Word) -> bool {
+        if self.config.min_distance <= 0.0 { return false };
+        self.real_words
+            .as_ref()
+            .and_then(|real_words| real_words.novelty(word))
+            .is_some_and(|novelty| novelty < self.config.min_distance)
+    }
+
+    /// How many words [`FakeWordGenerator::generate_word`] tries before giving up. 
+    const MAX_ATTEMPTS: usize = 100;
+
+    /// Generates a syllable beginning with `start`, following the restrictions in `config` and 
+    /// of the phonology. 
+    fn generate_syllable(&mut self, start: Phoneme) -> Option<Syllable> {
+        self.generate_syllable_with(start, None)
+    }
+
+    /// Generates a syllable like [`FakeWordGenerator::generate_syllable`] that `extra` also allows. 
+    fn generate_syllable_with(&mut self, start: Phoneme, extra: Option<SyllableFilter>) -> Option<Syllable> {
+        let enforce_sonority = self.config.enforce_sonority;
+        let allowed_syllables = &self.allowed_syllables;
+        let allow = |syl: &Syllable, next: NodeID| {
+            (!enforce_sonority || sonority::allows_next(syl, next))
+                && allowed_syllables.as_ref().is_none_or(|set| set.allows_next(syl, next))
+                && extra.is_none_or(|extra| extra(syl, next))
+        };
+        let next_syl = match enforce_sonority || allowed_syllables.is_some() || extra.is_some() {
+            true => self.sonority_graph.evaluate_from_start_with(start, &mut self.rng, &allow),
+            false => self.sonority_graph.evaluate_from_start(start, &mut self.rng),
+        };
+        next_syl.map(|result| result.0)
+    }
+
+    fn generate_candidate(&mut self) -> Word {
+        // the first syllable is steered towards whatever the batch is known to share so far
+        let batch = self.config.batch.clone();
+        let constrained = batch.is_active();
+        let first_allow = |syl: &Syllable, next: NodeID| batch.allows_next(syl, next);
+        let first = match constrained {
+            true => self.syllable_connections.evaluate_with(NodeData::Start, &mut self.rng, &|next| match next {
+                NodeData::Phoneme(phoneme) => batch.allows_next(&Syllable::empty(), SonorityGraph::start_id(phoneme)),
+                _ => false,
+            }),
+            false => self.syllable_connections.evaluate(NodeData::Start, &mut self.rng),
+        };
+        let mut cur_phone = first.unwrap_or(NodeData::Stop);
         let mut word = Word::empty();
 
         let mut new_syl_chance = 1.0;
@@ -219,9 +503,15 @@ impl FakeWordGenerator {
         while new_syl_chance > self.rng.gen_range(0f32..1f32) {
             match cur_phone {
                 NodeData::Phoneme(phone) => {
-                    let next_syl = self.sonority_graph.evaluate_from_start(phone, &mut self.rng).0;
+                    let next_syl = match constrained && i == 0 {
+                        true => self.generate_syllable_with(phone, Some(&first_allow)),
+                        false => self.generate_syllable(phone),
+                    };
+                    let Some(next_syl) = next_syl else { break };
                     word.add_syllable(next_syl.clone());
-                    cur_phone = self.syllable_connections.evaluate(NodeData::Phoneme(next_syl.last_phoneme()), &mut self.rng);
+                    cur_phone = self.syllable_connections
+                        .evaluate(NodeData::Phoneme(next_syl.last_phoneme()), &mut self.rng)
+                        .unwrap_or(NodeData::Stop);
                 },
                 NodeData::Start => {},
                 NodeData::Stop => { break },
@@ -233,4 +523,51 @@ impl FakeWordGenerator {
 
         word
     }
-}
\ No newline at end of file
+
+    /// Generates a word with exactly `count` syllables, never stopping early. 
+    /// 
+    /// Returns none if the model led somewhere no more syllables could follow. 
+    pub fn generate_syllables(&mut self, count: usize) -> Option<Word> {
+        let mut word = Word::empty();
+        let mut cur_phone = NodeData::Start;
+        for _ in 0..count {
+            let NodeData::Phoneme(start) = self.syllable_connections.evaluate_with(cur_phone, &mut self.rng, &|next| next != NodeData::Stop)? else { return None };
+            let syllable = self.generate_syllable(start)?;
+            cur_phone = NodeData::Phoneme(syllable.last_phoneme());
+            word.add_syllable(syllable);
+        }
+        Some(word)
+    }
+
+    /// Generates a word that rhymes from its stressed syllable on, steering the sonority graph 
+    /// towards the rhyme rather than generating words until one happens to rhyme. Words are as 
+    /// long as [`FakeWordGenerator::generate_word`] would make them, or as long as the rhyme, and 
+    /// are stressed where the rhyme starts. 
+    /// 
+    /// Returns none if no rhyming word was found in a few tries, or if every one was closer to 
+    /// a real word than `config.min_distance`. 
+    pub fn generate_rhyme(&mut self, rhyme: &Rhyme) -> Option<Word> {
+        'attempts: for _ in 0..Self::MAX_ATTEMPTS {
+            // borrows the length of an ordinary word, so rhyming words are as long as any other
+            let count = self.generate_candidate().syllables.len().max(rhyme.len());
+            let Some(mut word) = self.generate_syllables(count - rhyme.len()) else { continue };
+
+            for index in 0..rhyme.len() {
+                let allow = |syl: &Syllable, next: NodeID| rhyme.allows_next(index, syl, next);
+                let prev = word.syllables.last().map(|syl| NodeData::Phoneme(syl.last_phoneme())).unwrap_or(NodeData::Start);
+                let start = self.syllable_connections.evaluate_with(prev, &mut self.rng, &|next| match next {
+                    NodeData::Phoneme(phoneme) => allow(&Syllable::empty(), SonorityGraph::start_id(phoneme)),
+                    _ => false,
+                });
+                let Some(NodeData::Phoneme(start)) = start else { continue 'attempts };
+                let Some(syllable) = self.generate_syllable_with(start, Some(&allow)) else { continue 'attempts };
+                word.add_syllable(syllable);
+            }
+
+            if self.is_too_close(&word) { continue };
+            word.stress_syllable(count - rhyme.len());
+            return Some(word);
+        }
+        None
+    }
+}